@@ -0,0 +1,214 @@
+//! On-disk cache of license files retrieved from forges, so repeated runs
+//! do not have to query them again for the same crates.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use cargo::util::{Config, short_hash};
+use serde_json;
+
+use license::{Finding, LicenseId, LicenseSource};
+use lockfile;
+use read_file;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Serializing(serde_json::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(other: io::Error) -> Error {
+        Error::Io(other)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(other: serde_json::Error) -> Error {
+        Error::Serializing(other)
+    }
+}
+
+/// A crate version from a given source, as identified in `Cargo.lock`,
+/// along with the license whose file was looked for. The license chosen for a
+/// crate depends on the policy, so it may change between runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Key {
+    pub name: String,
+    pub version: String,
+    pub source: String,
+    pub license: LicenseId,
+}
+
+impl Key {
+    /// Crates without a source, such as path dependencies, are not cached
+    pub fn of(package: &lockfile::Package, license: LicenseId) -> Option<Key> {
+        Some(Key {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            source: try_opt!(package.source.clone()),
+            license,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub key: Key,
+    pub license_source: LicenseSource,
+
+    /// Where the license file was retrieved from, for revalidation
+    pub url: String,
+    pub etag: Option<String>,
+    pub license_text: String,
+
+    /// Findings from the lookup, which are reported again on cache hits
+    #[serde(default)]
+    pub findings: Vec<Finding>,
+}
+
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Cache {
+        Cache { dir: dir.into() }
+    }
+
+    /// `$XDG_CACHE_HOME/cargo-license-hound` when `XDG_CACHE_HOME` is set,
+    /// otherwise `license-hound` in Cargo's home directory
+    pub fn default_dir(config: &Config) -> PathBuf {
+        match env::var_os("XDG_CACHE_HOME") {
+            Some(dir) => PathBuf::from(dir).join("cargo-license-hound"),
+            None => config.home().join("license-hound").into_path_unlocked(),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path_of(&self, key: &Key) -> PathBuf {
+        self.dir
+            .join(short_hash(&key.source))
+            .join(format!("{}-{}-{}.json", key.name, key.version, key.license.spdx_id()))
+    }
+
+    pub fn get(&self, key: &Key) -> Option<Entry> {
+        let contents = try_opt!(read_file(self.path_of(key)).ok());
+        let entry: Entry = try_opt!(serde_json::from_str(&contents).ok());
+
+        if entry.key == *key { Some(entry) } else { None }
+    }
+
+    /// Stores `entry`, replacing any previous entry for the same crate and
+    /// license
+    pub fn put(&self, entry: &Entry) -> Result<(), Error> {
+        let path = self.path_of(&entry.key);
+        fs::create_dir_all(path.parent().unwrap())?;
+
+        // Write to a temporary file first so concurrent runs never see a
+        // partially written entry
+        let tmp_path = path.with_extension("json.tmp");
+        serde_json::to_writer_pretty(fs::File::create(&tmp_path)?, entry)?;
+        fs::rename(tmp_path, path)?;
+
+        Ok(())
+    }
+
+    /// All readable entries in the cache
+    pub fn entries(&self) -> Result<Vec<Entry>, Error> {
+        let sources = match fs::read_dir(&self.dir) {
+            Ok(sources) => sources,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut entries = vec![];
+        for source in sources {
+            for file in fs::read_dir(source?.path())? {
+                let path = file?.path();
+                if path.extension().map(|x| x != "json").unwrap_or(true) {
+                    continue;
+                }
+
+                if let Some(entry) = read_file(&path).ok().and_then(|x| serde_json::from_str(&x).ok()) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        entries.sort_by(|a: &Entry, b: &Entry| (&a.key.name, &a.key.version).cmp(&(&b.key.name, &b.key.version)));
+
+        Ok(entries)
+    }
+
+    /// Removes all entries, giving the number of entries removed
+    pub fn clear(&self) -> Result<usize, Error> {
+        let count = self.entries()?.len();
+
+        match fs::remove_dir_all(&self.dir) {
+            Ok(()) => Ok(count),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(count),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use license::LicenseOrigin;
+    use tempdir::TempDir;
+
+    fn entry(name: &str, version: &str) -> Entry {
+        Entry {
+            key: Key {
+                name: name.to_string(),
+                version: version.to_string(),
+                source: "registry+https://github.com/rust-lang/crates.io-index".to_string(),
+                license: LicenseId::Mit,
+            },
+            license_source: LicenseSource::new(
+                LicenseOrigin::GitHubRepo {
+                    url: format!("https://raw.githubusercontent.com/owner/{}/master/LICENSE", name),
+                    git_ref: "master".to_string(),
+                },
+                "Copyright (c) 2017 Someone",
+            ),
+            url: format!("https://raw.githubusercontent.com/owner/{}/master/LICENSE", name),
+            etag: Some("\"abc\"".to_string()),
+            license_text: "Copyright (c) 2017 Someone".to_string(),
+            findings: vec![],
+        }
+    }
+
+    #[test]
+    fn can_store_list_and_clear_entries() {
+        let dir = TempDir::new("cargo-license-hound-cache").unwrap();
+        let cache = Cache::new(dir.path().join("cache"));
+        assert!(cache.entries().unwrap().is_empty());
+
+        cache.put(&entry("b", "1.0.0")).unwrap();
+        cache.put(&entry("a", "0.1.0")).unwrap();
+
+        let cached = cache.get(&entry("a", "0.1.0").key).unwrap();
+        assert_eq!("Copyright (c) 2017 Someone", cached.license_text);
+        assert_eq!(Some("\"abc\"".to_string()), cached.etag);
+        assert!(cache.get(&entry("a", "0.2.0").key).is_none());
+
+        let names = cache.entries().unwrap().into_iter().map(|x| x.key.name).collect::<Vec<_>>();
+        assert_eq!(vec!["a", "b"], names);
+
+        // Another license is chosen when the policy changes
+        let mut apache = entry("a", "0.1.0").key;
+        apache.license = LicenseId::Apache2;
+        assert!(cache.get(&apache).is_none());
+
+        assert_eq!(2, cache.clear().unwrap());
+        assert!(cache.entries().unwrap().is_empty());
+    }
+}
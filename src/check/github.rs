@@ -0,0 +1,51 @@
+//! [Workflow commands](https://docs.github.com/en/actions/using-workflows/workflow-commands-for-github-actions)
+//! that make GitHub Actions show the violations as error annotations
+
+use std::io::{self, Write};
+
+use super::Violation;
+
+const LOCK_FILE: &str = "Cargo.lock";
+
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+pub fn write<W: Write>(out: &mut W, violations: &[Violation]) -> io::Result<()> {
+    for x in violations {
+        writeln!(
+            out,
+            "::error file={},title={}::{}",
+            LOCK_FILE,
+            escape_property(&format!("{} {}: {}", x.report.package_name, x.report.version, x.rule.id())),
+            escape_data(&x.message)
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use check::violations;
+    use config::Policy;
+    use output::fixture;
+
+    #[test]
+    fn can_write_workflow_commands() {
+        let reports = vec![fixture::unrecoverable("d")];
+
+        let mut out = vec![];
+        write(&mut out, &violations(&Policy::default(), &reports)).unwrap();
+
+        assert_eq!(
+            "::error file=Cargo.lock,title=d 1.0.0%3A missing-license-text::No license file found for d/Cargo.toml\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+}
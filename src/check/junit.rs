@@ -0,0 +1,76 @@
+//! A JUnit XML test report with a test case for each crate, which most CI
+//! systems can show
+
+use std::io::{self, Write};
+use std::ptr;
+
+use super::Violation;
+use output::html::escape;
+use output::sbom::TOOL_NAME;
+use LicenseReport;
+
+pub fn write<W: Write>(out: &mut W, reports: &[LicenseReport], violations: &[Violation]) -> io::Result<()> {
+    let failed = reports.iter()
+        .filter(|report| violations.iter().any(|x| ptr::eq(x.report, *report)))
+        .count();
+
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(out, "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\">", escape(TOOL_NAME), reports.len(), failed)?;
+    writeln!(out, "  <testsuite name=\"license-policy\" tests=\"{}\" failures=\"{}\">", reports.len(), failed)?;
+
+    for report in reports {
+        let name = format!("{} {}", report.package_name, report.version);
+        let failures = violations.iter()
+            .filter(|x| ptr::eq(x.report, report))
+            .collect::<Vec<_>>();
+
+        if failures.is_empty() {
+            writeln!(out, "    <testcase classname=\"crates\" name=\"{}\"/>", escape(&name))?;
+            continue;
+        }
+
+        writeln!(out, "    <testcase classname=\"crates\" name=\"{}\">", escape(&name))?;
+        for x in failures {
+            writeln!(out, "      <failure type=\"{}\" message=\"{}\"/>", x.rule.id(), escape(&x.message))?;
+        }
+        writeln!(out, "    </testcase>")?;
+    }
+
+    writeln!(out, "  </testsuite>")?;
+    writeln!(out, "</testsuites>")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use check::violations;
+    use config::Policy;
+    use license::LicenseId;
+    use output::fixture;
+
+    #[test]
+    fn can_write_test_report() {
+        let reports = vec![
+            fixture::ok("a", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 A"),
+            fixture::unrecoverable("d"),
+        ];
+
+        let mut out = vec![];
+        write(&mut out, &reports, &violations(&Policy::default(), &reports)).unwrap();
+
+        let expected = [
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>",
+            &format!("<testsuites name=\"{}\" tests=\"2\" failures=\"1\">", TOOL_NAME),
+            "  <testsuite name=\"license-policy\" tests=\"2\" failures=\"1\">",
+            "    <testcase classname=\"crates\" name=\"a 1.0.0\"/>",
+            "    <testcase classname=\"crates\" name=\"d 1.0.0\">",
+            "      <failure type=\"missing-license-text\" message=\"No license file found for d/Cargo.toml\"/>",
+            "    </testcase>",
+            "  </testsuite>",
+            "</testsuites>",
+            "",
+        ];
+
+        assert_eq!(expected.join("\n"), String::from_utf8(out).unwrap());
+    }
+}
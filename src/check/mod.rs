@@ -0,0 +1,446 @@
+//! Holding the reports to the policy, for gating merges in CI
+
+mod github;
+mod junit;
+mod sarif;
+
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use config::Policy;
+use expression::{self, Expression};
+use license::{self, Category, LicenseId};
+use {LicenseError, LicenseReport};
+
+/// The rules of the policy, which violations are reported by
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Rule {
+    LicenseNotAllowed,
+    LicenseDenied,
+    UnacceptableLicense,
+    LicenseNotDeclared,
+    MissingLicenseText,
+    MissingCopyrightNotice,
+    LicenseMismatch,
+}
+
+const RULES: &[Rule] = &[
+    Rule::LicenseNotAllowed,
+    Rule::LicenseDenied,
+    Rule::UnacceptableLicense,
+    Rule::LicenseNotDeclared,
+    Rule::MissingLicenseText,
+    Rule::MissingCopyrightNotice,
+    Rule::LicenseMismatch,
+];
+
+impl Rule {
+    pub fn id(&self) -> &'static str {
+        match *self {
+            Rule::LicenseNotAllowed => "license-not-allowed",
+            Rule::LicenseDenied => "license-denied",
+            Rule::UnacceptableLicense => "unacceptable-license",
+            Rule::LicenseNotDeclared => "license-not-declared",
+            Rule::MissingLicenseText => "missing-license-text",
+            Rule::MissingCopyrightNotice => "missing-copyright-notice",
+            Rule::LicenseMismatch => "license-mismatch",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match *self {
+            Rule::LicenseNotAllowed => "The license is not among the allowed licenses",
+            Rule::LicenseDenied => "The license is denied by the policy",
+            Rule::UnacceptableLicense => "The license is not one license-hound can settle on",
+            Rule::LicenseNotDeclared => "The crate manifest does not declare a license",
+            Rule::MissingLicenseText => "The license text could not be recovered",
+            Rule::MissingCopyrightNotice => "The license text has no copyright notice",
+            Rule::LicenseMismatch => "A license detection service disagrees with the crate manifest",
+        }
+    }
+}
+
+/// A crate breaking a rule of the policy
+pub struct Violation<'a> {
+    pub report: &'a LicenseReport,
+    pub rule: Rule,
+    pub message: String,
+}
+
+/// Why a single license is not acceptable, if it is not. Licenses are
+/// matched against the policy like `license::same_license` does.
+fn objection(policy: &Policy, id: &str, exception: Option<&str>) -> Option<(Rule, String)> {
+    let category = Category::of(id, exception);
+
+    if policy.deny.iter().any(|x| license::same_license(x, id)) {
+        Some((Rule::LicenseDenied, format!("{} is denied", id)))
+    } else if policy.deny_categories.contains(&category) {
+        Some((Rule::LicenseDenied, format!("{} is {}, which is denied", id, category.id())))
+    } else if !policy.allow.is_empty() && !policy.allow.iter().any(|x| license::same_license(x, id)) {
+        Some((Rule::LicenseNotAllowed, format!("{} is not among the allowed licenses", id)))
+    } else {
+        None
+    }
+}
+
+/// The objections to a license expression. There are none if the licenses
+/// that must be complied with are acceptable, where choosing any acceptable
+/// alternative of an `OR` suffices.
+fn objections(policy: &Policy, expression: &Expression) -> Vec<(Rule, String)> {
+    match expression {
+        &Expression::License { ref id, ref exception } =>
+            objection(policy, id, exception.as_ref().map(|x| &x[..])).into_iter().collect(),
+        &Expression::And(ref terms) =>
+            terms.iter().flat_map(|x| objections(policy, x)).collect(),
+        &Expression::Or(ref terms) => {
+            let alternatives = terms.iter().map(|x| objections(policy, x)).collect::<Vec<_>>();
+            if alternatives.iter().any(|x| x.is_empty()) {
+                vec![]
+            } else {
+                alternatives.into_iter().flatten().collect()
+            }
+        },
+    }
+}
+
+/// Whether every alternative of the declared license expression requires
+/// complying with more than one license
+fn combines_licenses(declared: &str) -> bool {
+    expression::parse(declared)
+        .map(|x| x.alternatives().iter().all(|alternative| alternative.len() > 1))
+        .unwrap_or(false)
+}
+
+/// Chooses the license to settle on for a crate declaring `expression`. Of
+/// the alternatives that consist of a license license-hound can settle on,
+/// those acceptable to the policy are preferred, and then the most preferred
+/// license of `license::SUPPORTED_LICENSES`. Alternatives combining several
+/// licenses, as in `MIT AND Zlib`, are never chosen: all of their licenses
+/// apply, while only one license is attributed.
+pub fn choose_license(policy: &Policy, expression: &Expression) -> Option<LicenseId> {
+    expression.alternatives().into_iter()
+        .filter_map(|alternative| {
+            let (rank, chosen) = try_opt!(license::SUPPORTED_LICENSES.iter().cloned().enumerate()
+                .find(|&(_, x)| alternative.iter().all(|term| match term {
+                    &&Expression::License { ref id, exception: None } => license::same_license(id, x.spdx_id()),
+                    _ => false,
+                })));
+            let all = Expression::And(alternative.into_iter().cloned().collect());
+            Some((!objections(policy, &all).is_empty(), rank, chosen))
+        })
+        .min_by_key(|&(objected, rank, _)| (objected, rank))
+        .map(|(_, _, chosen)| chosen)
+}
+
+/// Checks every crate against the policy. Crates without a source, such as
+/// the crates of the workspace itself, and crates from registries that are
+/// not audited are left alone.
+pub fn violations<'a>(policy: &Policy, reports: &'a [LicenseReport]) -> Vec<Violation<'a>> {
+    let mut violations = vec![];
+
+    for report in reports {
+        let mut violation = |rule, message| violations.push(Violation { report, rule, message });
+
+        let description = match report.conclusion {
+            Ok(ref description) => description,
+            Err(LicenseError::NoSource) | Err(LicenseError::RegistryNotAudited(_)) => continue,
+            Err(LicenseError::LicenseNotDeclared(ref path)) => {
+                violation(Rule::LicenseNotDeclared, format!("No license declared in {}", path.display()));
+                continue;
+            },
+            Err(LicenseError::UnacceptableLicense(ref declared)) => {
+                if let Some(declared) = expression::parse(declared) {
+                    for (rule, message) in objections(policy, &declared) {
+                        violation(rule, message);
+                    }
+                }
+                let message = if combines_licenses(declared) {
+                    format!("{} combines licenses that would all need to be attributed, which license-hound cannot do", declared)
+                } else {
+                    format!("{} is not a license license-hound can settle on", declared)
+                };
+                violation(Rule::UnacceptableLicense, message);
+                continue;
+            },
+            Err(LicenseError::UnableToRecoverLicenseFile(ref path)) => {
+                if policy.require_license_text {
+                    violation(Rule::MissingLicenseText, format!("No license file found for {}", path.display()));
+                }
+                continue;
+            },
+            Err(LicenseError::UnableToRecoverAttribution(_)) => {
+                if policy.require_copyright_notice {
+                    violation(Rule::MissingCopyrightNotice, "No copyright notice found in the license text".to_string());
+                }
+                continue;
+            },
+            Err(LicenseError::LicenseMismatch(ref detected)) => {
+                violation(Rule::LicenseMismatch, format!("The license was detected as {}, unlike in the crate manifest", detected));
+                continue;
+            },
+        };
+
+        // The license expression of the manifest is what the crate may be
+        // used under. Should it not parse, the chosen license is all there
+        // is to go by.
+        let chosen = Expression::License { id: description.chosen_license.spdx_id().to_string(), exception: None };
+        let declared = expression::parse(&description.full_spdx_license).unwrap_or(chosen);
+        for (rule, message) in objections(policy, &declared) {
+            violation(rule, message);
+        }
+
+        if policy.require_license_text && description.full_license_document.trim().is_empty() {
+            violation(Rule::MissingLicenseText, "The license file is empty".to_string());
+        }
+
+        if policy.require_copyright_notice && description.copyright_notice.trim().is_empty() {
+            violation(Rule::MissingCopyrightNotice, "The copyright notice is empty".to_string());
+        }
+    }
+
+    violations
+}
+
+/// Writes the violations one per line, followed by a count
+pub fn write_summary<W: Write>(out: &mut W, violations: &[Violation]) -> io::Result<()> {
+    for x in violations {
+        writeln!(out, "ERROR {} {}: {}: {}", x.report.package_name, x.report.version, x.rule.id(), x.message)?;
+    }
+
+    if violations.is_empty() {
+        writeln!(out, "No policy violations")
+    } else {
+        writeln!(out, "{} policy violations", violations.len())
+    }
+}
+
+/// Machine-readable forms of the violations, for CI systems to show
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Annotations {
+    /// Workflow commands for GitHub Actions
+    GitHub,
+
+    /// A JUnit XML test report, with a test case for each crate
+    JUnit,
+
+    /// A SARIF 2.1.0 log, for code scanning
+    Sarif,
+}
+
+const ANNOTATIONS: &[(&str, Annotations)] = &[
+    ("github", Annotations::GitHub),
+    ("junit", Annotations::JUnit),
+    ("sarif", Annotations::Sarif),
+];
+
+impl FromStr for Annotations {
+    type Err = UnknownAnnotations;
+
+    fn from_str(s: &str) -> Result<Annotations, UnknownAnnotations> {
+        ANNOTATIONS.iter()
+            .find(|&&(name, _)| name == s)
+            .map(|&(_, annotations)| annotations)
+            .ok_or_else(|| UnknownAnnotations(s.to_string()))
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownAnnotations(String);
+
+impl fmt::Display for UnknownAnnotations {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown annotations format {:?}. Expected one of:", self.0)?;
+        for &(name, _) in ANNOTATIONS {
+            write!(f, " {}", name)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn write_annotations<W: Write>(out: &mut W, annotations: Annotations, reports: &[LicenseReport], violations: &[Violation]) -> io::Result<()> {
+    match annotations {
+        Annotations::GitHub => github::write(out, violations),
+        Annotations::JUnit => junit::write(out, reports, violations),
+        Annotations::Sarif => sarif::write(out, violations),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use license::LicenseId;
+    use output::fixture;
+
+    #[test]
+    fn can_find_violations() {
+        let mut reports = vec![
+            fixture::ok("a", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 A"),
+            fixture::ok("b", "1.0.0", LicenseId::Mpl2, "Copyright (c) 2017 B"),
+            fixture::ok("c", "1.0.0", LicenseId::Bsd3Clause, "Copyright (c) 2017 C"),
+            fixture::unrecoverable("d"),
+            fixture::unrecoverable("workspace"),
+        ];
+        reports[4].conclusion = Err(LicenseError::NoSource);
+
+        let policy = Policy {
+            allow: vec!["MIT".to_string(), "MPL-2.0".to_string()],
+            deny: vec!["MPL-2.0".to_string()],
+            ..Policy::default()
+        };
+
+        let found = violations(&policy, &reports).into_iter()
+            .map(|x| (&x.report.package_name[..], x.rule))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("b", Rule::LicenseDenied),
+                ("c", Rule::LicenseNotAllowed),
+                ("d", Rule::MissingLicenseText),
+            ],
+            found
+        );
+
+        let lenient = Policy { require_license_text: false, ..Policy::default() };
+        assert!(violations(&lenient, &reports).is_empty());
+    }
+
+    #[test]
+    fn alternatives_can_satisfy_the_policy() {
+        let mut reports = vec![
+            fixture::ok("a", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 A"),
+            fixture::ok("b", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 B"),
+            fixture::ok("c", "1.0.0", LicenseId::Mpl2, "Copyright (c) 2017 C"),
+            fixture::ok("d", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 D"),
+        ];
+        reports[0].conclusion.as_mut().unwrap().full_spdx_license = "MIT OR GPL-3.0-or-later".to_string();
+        reports[1].conclusion.as_mut().unwrap().full_spdx_license = "MIT AND AGPL-3.0-only".to_string();
+        reports[2].conclusion.as_mut().unwrap().full_spdx_license = "MPL-2.0/LGPL-2.1+".to_string();
+        reports[3].conclusion.as_mut().unwrap().full_spdx_license = "GPL-2.0 WITH Classpath-exception-2.0".to_string();
+
+        let policy = Policy {
+            deny_categories: vec![Category::StrongCopyleft, Category::NetworkCopyleft],
+            ..Policy::default()
+        };
+
+        let found = violations(&policy, &reports).into_iter()
+            .map(|x| (&x.report.package_name[..], x.rule, x.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![("b", Rule::LicenseDenied, "AGPL-3.0-only is network-copyleft, which is denied".to_string())],
+            found
+        );
+
+        let policy = Policy {
+            deny_categories: vec![Category::WeakCopyleft],
+            ..Policy::default()
+        };
+
+        let found = violations(&policy, &reports).into_iter()
+            .map(|x| (&x.report.package_name[..], x.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("c", "MPL-2.0 is weak-copyleft, which is denied".to_string()),
+                ("c", "LGPL-2.1+ is weak-copyleft, which is denied".to_string()),
+                ("d", "GPL-2.0 is weak-copyleft, which is denied".to_string()),
+            ],
+            found
+        );
+    }
+
+    #[test]
+    fn reports_denied_licenses_that_can_not_be_settled_on() {
+        let mut reports = vec![fixture::unrecoverable("a")];
+        reports[0].conclusion = Err(LicenseError::UnacceptableLicense("GPL-3.0-only".to_string()));
+
+        let policy = Policy {
+            deny_categories: vec![Category::StrongCopyleft],
+            ..Policy::default()
+        };
+
+        let found = violations(&policy, &reports).into_iter()
+            .map(|x| (x.rule, x.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (Rule::LicenseDenied, "GPL-3.0-only is strong-copyleft, which is denied".to_string()),
+                (Rule::UnacceptableLicense, "GPL-3.0-only is not a license license-hound can settle on".to_string()),
+            ],
+            found
+        );
+    }
+
+    #[test]
+    fn explains_why_combined_licenses_can_not_be_settled_on() {
+        let mut reports = vec![fixture::unrecoverable("a")];
+        reports[0].conclusion = Err(LicenseError::UnacceptableLicense("MIT AND Apache-2.0".to_string()));
+
+        let found = violations(&Policy::default(), &reports).into_iter()
+            .map(|x| (x.rule, x.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![(
+                Rule::UnacceptableLicense,
+                "MIT AND Apache-2.0 combines licenses that would all need to be attributed, which license-hound cannot do".to_string(),
+            )],
+            found
+        );
+    }
+
+    #[test]
+    fn chooses_a_license_the_policy_accepts() {
+        let choose = |policy: &Policy, declared: &str| choose_license(policy, &expression::parse(declared).unwrap());
+
+        let lenient = Policy::default();
+        assert_eq!(Some(LicenseId::Mit), choose(&lenient, "MIT OR Apache-2.0"));
+        assert_eq!(Some(LicenseId::Apache2), choose(&lenient, "Apache-2.0"));
+        assert_eq!(None, choose(&lenient, "MIT AND GPL-3.0-only"));
+        assert_eq!(None, choose(&lenient, "MIT AND Apache-2.0"));
+        assert_eq!(Some(LicenseId::Apache2), choose(&lenient, "(MIT AND Zlib) OR Apache-2.0"));
+        assert_eq!(None, choose(&lenient, "GPL-3.0-only"));
+        assert_eq!(None, choose(&lenient, "MIT WITH LLVM-exception"));
+
+        let apache = Policy { allow: vec!["Apache-2.0".to_string()], ..Policy::default() };
+        assert_eq!(Some(LicenseId::Apache2), choose(&apache, "MIT OR Apache-2.0"));
+        assert_eq!(Some(LicenseId::Mit), choose(&apache, "MIT"));
+
+        let permissive = Policy { deny_categories: vec![Category::StrongCopyleft], ..Policy::default() };
+        assert_eq!(Some(LicenseId::Mpl2), choose(&permissive, "(MIT AND GPL-3.0-only) OR MPL-2.0"));
+        assert_eq!(Some(LicenseId::Mpl2), choose(&lenient, "(MIT AND GPL-3.0-only) OR MPL-2.0"));
+    }
+
+    #[test]
+    fn matches_license_ids_like_spdx() {
+        let policy = Policy {
+            allow: vec!["mit".to_string(), "LGPL-2.1+".to_string()],
+            deny: vec!["GPL-3.0".to_string()],
+            ..Policy::default()
+        };
+
+        for id in &["GPL-3.0", "GPL-3.0-only", "GPL-3.0-or-later", "GPL-3.0+", "gpl-3.0"] {
+            assert_eq!(Some(Rule::LicenseDenied), objection(&policy, id, None).map(|x| x.0), "{}", id);
+        }
+
+        for id in &["MIT", "LGPL-2.1-only", "LGPL-2.1-or-later", "lgpl-2.1"] {
+            assert_eq!(None, objection(&policy, id, None), "{}", id);
+        }
+
+        assert_eq!(Some(Rule::LicenseNotAllowed), objection(&policy, "MIT-0", None).map(|x| x.0));
+        assert_eq!(Some(Rule::LicenseNotAllowed), objection(&policy, "AGPL-3.0", None).map(|x| x.0));
+    }
+
+    #[test]
+    fn can_summarize_violations() {
+        let reports = vec![fixture::unrecoverable("d")];
+
+        let mut out = vec![];
+        write_summary(&mut out, &violations(&Policy::default(), &reports)).unwrap();
+
+        assert_eq!(
+            "ERROR d 1.0.0: missing-license-text: No license file found for d/Cargo.toml\n\
+            1 policy violations\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+}
@@ -0,0 +1,77 @@
+//! A [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html)
+//! log, for code scanning services such as GitHub's
+
+use std::io::{self, Write};
+
+use serde_json::{self, Value};
+
+use super::{Violation, RULES};
+use output::sbom::{TOOL_NAME, TOOL_VERSION};
+
+const LOCK_FILE: &str = "Cargo.lock";
+
+fn log(violations: &[Violation]) -> Value {
+    let rules = RULES.iter()
+        .map(|rule| json!({
+            "id": rule.id(),
+            "shortDescription": { "text": rule.description() },
+        }))
+        .collect::<Vec<_>>();
+
+    let results = violations.iter()
+        .map(|x| json!({
+            "ruleId": x.rule.id(),
+            "level": "error",
+            "message": {
+                "text": format!("{} {}: {}", x.report.package_name, x.report.version, x.message),
+            },
+            "locations": [{
+                "physicalLocation": {
+                    "artifactLocation": { "uri": LOCK_FILE },
+                },
+            }],
+        }))
+        .collect::<Vec<_>>();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": TOOL_NAME,
+                    "version": TOOL_VERSION,
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+pub fn write<W: Write>(out: &mut W, violations: &[Violation]) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, &log(violations))?;
+    writeln!(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use check::violations;
+    use config::Policy;
+    use output::fixture;
+
+    #[test]
+    fn can_write_sarif_log() {
+        let reports = vec![fixture::unrecoverable("d")];
+        let log = log(&violations(&Policy::default(), &reports));
+
+        assert_eq!(json!("2.1.0"), log["version"]);
+        assert_eq!(json!(RULES.len()), json!(log["runs"][0]["tool"]["driver"]["rules"].as_array().unwrap().len()));
+        assert_eq!(json!("missing-license-text"), log["runs"][0]["results"][0]["ruleId"]);
+        assert_eq!(
+            json!("d 1.0.0: No license file found for d/Cargo.toml"),
+            log["runs"][0]["results"][0]["message"]["text"]
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use toml;
+
+use license::Category;
+
+pub const CONFIG_FILE: &str = "license-hound.toml";
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Deserializing(toml::de::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(other: io::Error) -> Error {
+        Error::Io(other)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(other: toml::de::Error) -> Error {
+        Error::Deserializing(other)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Error::Io(ref err) => write!(f, "{}", err),
+            &Error::Deserializing(ref err) => write!(f, "Not a valid configuration: {}", err),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegistryPolicy {
+    /// Set to `false` to skip hounding licenses for crates from this
+    /// registry, for example for an internal registry of in-house crates.
+    #[serde(default = "default_true")]
+    pub audit: bool,
+}
+
+/// How to treat a license detection service disagreeing with the license
+/// chosen from the crate manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MismatchPolicy {
+    /// Record the disagreement as a finding and keep looking for a license
+    /// file matching the manifest
+    Warn,
+
+    /// Record the disagreement and fail the package with `LicenseMismatch`
+    Error,
+}
+
+impl Default for MismatchPolicy {
+    fn default() -> MismatchPolicy {
+        MismatchPolicy::Warn
+    }
+}
+
+/// What `check` holds the crates to, besides what the hound requires
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Policy {
+    #[serde(default)]
+    pub license_mismatch: MismatchPolicy,
+
+    /// SPDX identifiers of the licenses crates may be used under. When
+    /// empty, any license the hound settles on is allowed.
+    #[serde(default)]
+    pub allow: Vec<String>,
+
+    /// SPDX identifiers of licenses crates may not be used under
+    #[serde(default)]
+    pub deny: Vec<String>,
+
+    /// Categories of licenses crates may not be used under, such as
+    /// `strong-copyleft`
+    #[serde(default)]
+    pub deny_categories: Vec<Category>,
+
+    /// Crates must come with the text of their license
+    #[serde(default = "default_true")]
+    pub require_license_text: bool,
+
+    /// The license text of crates must include a copyright notice
+    #[serde(default = "default_true")]
+    pub require_copyright_notice: bool,
+}
+
+impl Default for Policy {
+    fn default() -> Policy {
+        Policy {
+            license_mismatch: MismatchPolicy::default(),
+            allow: vec![],
+            deny: vec![],
+            deny_categories: vec![],
+            require_license_text: true,
+            require_copyright_notice: true,
+        }
+    }
+}
+
+/// Where to find a GitHub instance and how to authenticate with it. All
+/// fields are optional for `[github]`, which defaults to github.com, while
+/// `[[github-enterprise]]` entries must give `web-url`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct GitHubConfig {
+    /// The base of repository URLs, such as `https://github.example.com`
+    pub web_url: Option<String>,
+
+    /// The base of the REST API, such as `https://github.example.com/api/v3`
+    pub api_url: Option<String>,
+
+    /// The base of raw file URLs, such as `https://github.example.com/raw`
+    pub raw_url: Option<String>,
+
+    pub token: Option<String>,
+}
+
+/// A self-hosted GitLab, Gitea or Forgejo instance, as given in `[[gitlab]]`
+/// and `[[gitea]]` sections
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ForgeConfig {
+    /// The base of repository URLs, such as `https://gitlab.example.com`
+    pub web_url: String,
+
+    pub token: Option<String>,
+}
+
+/// Configuration for license-hound itself, read from `license-hound.toml`
+/// in the project directory.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HoundConfig {
+    /// Policy per registry, keyed by the registry name as configured in
+    /// `.cargo/config`, or `crates-io` for the default registry.
+    #[serde(default)]
+    pub registries: HashMap<String, RegistryPolicy>,
+
+    #[serde(default)]
+    pub github: GitHubConfig,
+
+    #[serde(default)]
+    pub github_enterprise: Vec<GitHubConfig>,
+
+    #[serde(default)]
+    pub gitlab: Vec<ForgeConfig>,
+
+    #[serde(default)]
+    pub gitea: Vec<ForgeConfig>,
+
+    #[serde(default)]
+    pub policy: Policy,
+}
+
+impl HoundConfig {
+    /// Reads the configuration file, falling back to the default
+    /// configuration if the file does not exist.
+    pub fn from_file<P: AsRef<Path>>(f: P) -> Result<HoundConfig, Error> {
+        use std::fs::File;
+        use std::io::Read;
+
+        let file = match File::open(f) {
+            Ok(file) => file,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(HoundConfig::default()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut reader = io::BufReader::new(file);
+        let mut buf = String::new();
+        reader.read_to_string(&mut buf)?;
+
+        Ok(toml::from_str(&buf)?)
+    }
+
+    pub fn audits_registry(&self, registry: &str) -> bool {
+        self.registries.get(registry).map(|x| x.audit).unwrap_or(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn can_read_registry_policies() {
+        let config: HoundConfig = toml::from_str(r#"
+            [registries.our-registry]
+            audit = false
+
+            [registries.crates-io]
+        "#).unwrap();
+
+        assert!(!config.audits_registry("our-registry"));
+        assert!(config.audits_registry("crates-io"));
+        assert!(config.audits_registry("unconfigured"));
+    }
+
+    #[test]
+    fn can_read_self_hosted_forges() {
+        let config: HoundConfig = toml::from_str(r#"
+            [[gitlab]]
+            web-url = "https://gitlab.example.com"
+            token = "secret"
+
+            [[gitea]]
+            web-url = "https://gitea.example.com"
+        "#).unwrap();
+
+        assert_eq!("https://gitlab.example.com", config.gitlab[0].web_url);
+        assert_eq!(Some("secret".to_string()), config.gitlab[0].token);
+        assert_eq!("https://gitea.example.com", config.gitea[0].web_url);
+        assert_eq!(None, config.gitea[0].token);
+
+        assert!(toml::from_str::<HoundConfig>("[[gitea]]\ntoken = \"secret\"").is_err());
+    }
+}
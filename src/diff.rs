@@ -0,0 +1,473 @@
+//! What changed license-wise between two sets of reports, such as before
+//! and after a dependency bump
+
+use std::cmp;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::{self, Value};
+
+use output::{sbom, Status};
+use report::{LoadError, Report};
+use {read_file, LicenseReport};
+
+/// Unchanged lines shown around changed lines in text diffs
+const CONTEXT: usize = 2;
+
+#[derive(Debug)]
+pub enum InputError {
+    Io(io::Error),
+
+    /// `git show` failed, with this message
+    Git(String),
+
+    Report(LoadError),
+}
+
+impl From<io::Error> for InputError {
+    fn from(other: io::Error) -> InputError {
+        InputError::Io(other)
+    }
+}
+
+impl From<LoadError> for InputError {
+    fn from(other: LoadError) -> InputError {
+        InputError::Report(other)
+    }
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &InputError::Io(ref err) => write!(f, "{}", err),
+            &InputError::Git(ref message) => write!(f, "git show failed: {}", message),
+            &InputError::Report(ref err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// One side of the comparison
+pub enum Input {
+    /// A report saved with `--format json`
+    Report(Report),
+
+    /// The contents of a `Cargo.lock`, for which the licenses are yet to be
+    /// hounded
+    LockFile(String),
+}
+
+fn git_show(object: &str) -> Result<String, InputError> {
+    let output = Command::new("git").arg("show").arg(object).output()?;
+    if !output.status.success() {
+        return Err(InputError::Git(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+impl Input {
+    /// Reads `spec`, which is a path to a report or lock file, a git object
+    /// like `main:Cargo.lock`, or a git revision like `main`, meaning the
+    /// `Cargo.lock` in the current directory as of that revision. Any JSON
+    /// is read as a report, anything else as a lock file.
+    pub fn read(spec: &str) -> Result<Input, InputError> {
+        let contents =
+            if Path::new(spec).exists() {
+                read_file(spec)?
+            } else if spec.contains(':') {
+                git_show(spec)?
+            } else {
+                git_show(&format!("{}:./Cargo.lock", spec))?
+            };
+
+        // Lock files may also start with `[`, so only content that parses as
+        // JSON counts, except for malformed objects
+        let is_json = contents.trim_start().starts_with('{') || serde_json::from_str::<Value>(&contents).is_ok();
+
+        if is_json {
+            Ok(Input::Report(Report::from_json(&contents)?))
+        } else {
+            Ok(Input::LockFile(contents))
+        }
+    }
+}
+
+/// The crates of two sets of reports, matched up
+pub struct Changes<'a> {
+    pub added: Vec<&'a LicenseReport>,
+    pub removed: Vec<&'a LicenseReport>,
+
+    /// Crates in both, including crates updated to another version
+    pub kept: Vec<(&'a LicenseReport, &'a LicenseReport)>,
+}
+
+/// Crates are matched by name and version. Of the remaining crates, a crate
+/// is considered updated when there is only one version of it on each side.
+pub fn compare<'a>(old: &'a [LicenseReport], new: &'a [LicenseReport]) -> Changes<'a> {
+    let same_crate = |a: &LicenseReport, b: &LicenseReport| a.package_name == b.package_name && a.version == b.version;
+
+    let mut kept = new.iter()
+        .filter_map(|x| old.iter().find(|y| same_crate(x, y)).map(|y| (y, x)))
+        .collect::<Vec<_>>();
+
+    let mut removed = old.iter().filter(|x| !new.iter().any(|y| same_crate(x, y))).collect::<Vec<_>>();
+    let mut added = new.iter().filter(|x| !old.iter().any(|y| same_crate(x, y))).collect::<Vec<_>>();
+
+    let updated = added.iter()
+        .filter_map(|x| {
+            let named = |y: &&&LicenseReport| y.package_name == x.package_name;
+            match (removed.iter().filter(named).count(), added.iter().filter(named).count()) {
+                (1, 1) => Some((*removed.iter().find(named).unwrap(), *x)),
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    removed.retain(|x| !updated.iter().any(|y| same_crate(x, y.0)));
+    added.retain(|x| !updated.iter().any(|y| same_crate(x, y.1)));
+    kept.extend(updated);
+
+    let key = |x: &LicenseReport| (x.package_name.clone(), x.version.clone());
+    added.sort_by_key(|x| key(x));
+    removed.sort_by_key(|x| key(x));
+    kept.sort_by_key(|x| key(x.1));
+
+    Changes { added, removed, kept }
+}
+
+pub enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// A line by line diff, ignoring trailing whitespace. Based on the longest
+/// common subsequence, which is fine for texts the size of licenses.
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<Line<'a>> {
+    let old = old.lines().map(|x| x.trim_end()).collect::<Vec<_>>();
+    let new = new.lines().map(|x| x.trim_end()).collect::<Vec<_>>();
+
+    // lcs[i][j] is the length of the longest common subsequence of
+    // old[i..] and new[j..]
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] =
+                if old[i] == new[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    cmp::max(lcs[i + 1][j], lcs[i][j + 1])
+                };
+        }
+    }
+
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push(Line::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(Line::Removed(old[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(new[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(|x| Line::Removed(x)));
+    lines.extend(new[j..].iter().map(|x| Line::Added(x)));
+
+    lines
+}
+
+fn same_text(old: &str, new: &str) -> bool {
+    let lines = |x: &str| x.trim_matches('\n').lines().map(|x| x.trim_end().to_string()).collect::<Vec<_>>();
+    lines(old) == lines(new)
+}
+
+/// The declared license, and the chosen license where a choice was made.
+/// `None` when hounding failed before the license was known.
+fn known_license_of(report: &LicenseReport) -> Option<String> {
+    let declared = try_opt!(sbom::declared_text(report));
+
+    Some(match sbom::concluded_license(report) {
+        Some(concluded) if concluded != declared => format!("{} (using {})", declared, concluded),
+        _ => declared,
+    })
+}
+
+fn license_of(report: &LicenseReport) -> String {
+    known_license_of(report).unwrap_or_else(|| "unknown".to_string())
+}
+
+fn status_of(report: &LicenseReport) -> String {
+    let status = Status::of(report);
+    match status {
+        Status::Ok => status.label().to_string(),
+        Status::Warn(ref detail) | Status::Error(ref detail) => format!("{} {}", status.label(), detail),
+    }
+}
+
+fn label(old: &LicenseReport, new: &LicenseReport) -> String {
+    if old.version == new.version {
+        format!("{} {}", new.package_name, new.version)
+    } else {
+        format!("{} {} -> {}", new.package_name, old.version, new.version)
+    }
+}
+
+fn write_heading<W: Write>(out: &mut W, heading: &str, first: &mut bool) -> io::Result<()> {
+    if !*first {
+        writeln!(out)?;
+    }
+    *first = false;
+    writeln!(out, "{}", heading)
+}
+
+fn write_text_diff<W: Write>(out: &mut W, old: &str, new: &str) -> io::Result<()> {
+    let lines = diff_lines(old.trim_matches('\n'), new.trim_matches('\n'));
+
+    let changed = lines.iter()
+        .map(|x| match *x { Line::Same(_) => false, _ => true })
+        .collect::<Vec<_>>();
+    let shown = (0..lines.len())
+        .map(|i| {
+            let from = i.saturating_sub(CONTEXT);
+            let to = cmp::min(i + CONTEXT + 1, lines.len());
+            changed[from..to].iter().any(|x| *x)
+        })
+        .collect::<Vec<_>>();
+
+    for (i, line) in lines.iter().enumerate() {
+        if !shown[i] {
+            if i == 0 || shown[i - 1] {
+                writeln!(out, "    ...")?;
+            }
+            continue;
+        }
+
+        let (marker, text) = match *line {
+            Line::Same(x) => (' ', x),
+            Line::Removed(x) => ('-', x),
+            Line::Added(x) => ('+', x),
+        };
+        writeln!(out, "{}", format!("    {} {}", marker, text).trim_end())?;
+    }
+
+    Ok(())
+}
+
+/// Writes the changes as a changelog, by category
+pub fn write<W: Write>(out: &mut W, changes: &Changes) -> io::Result<()> {
+    let mut first = true;
+
+    if !changes.added.is_empty() {
+        write_heading(out, "Added crates", &mut first)?;
+        for x in &changes.added {
+            writeln!(out, "  + {} {}: {}", x.package_name, x.version, license_of(x))?;
+        }
+    }
+
+    if !changes.removed.is_empty() {
+        write_heading(out, "Removed crates", &mut first)?;
+        for x in &changes.removed {
+            writeln!(out, "  - {} {}: {}", x.package_name, x.version, license_of(x))?;
+        }
+    }
+
+    // Failing to find the license is a change of status, which may well be
+    // transient, rather than a change of license
+    let license_changes = changes.kept.iter()
+        .filter(|&&(old, new)| match (known_license_of(old), known_license_of(new)) {
+            (Some(old), Some(new)) => old != new,
+            _ => false,
+        })
+        .collect::<Vec<_>>();
+    if !license_changes.is_empty() {
+        write_heading(out, "License changes", &mut first)?;
+        for &&(old, new) in &license_changes {
+            writeln!(out, "  {}: {} -> {}", label(old, new), license_of(old), license_of(new))?;
+        }
+    }
+
+    let status_changes = changes.kept.iter()
+        .filter(|&&(old, new)| status_of(old) != status_of(new))
+        .collect::<Vec<_>>();
+    if !status_changes.is_empty() {
+        write_heading(out, "Status changes", &mut first)?;
+        for &&(old, new) in &status_changes {
+            writeln!(out, "  {}: {} -> {}", label(old, new), status_of(old), status_of(new))?;
+        }
+    }
+
+    let descriptions = changes.kept.iter()
+        .filter_map(|&(old, new)| match (&old.conclusion, &new.conclusion) {
+            (&Ok(ref a), &Ok(ref b)) => Some((old, new, a, b)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let copyright_changes = descriptions.iter()
+        .filter(|x| x.2.copyright_notice != x.3.copyright_notice)
+        .collect::<Vec<_>>();
+    if !copyright_changes.is_empty() {
+        write_heading(out, "Copyright changes", &mut first)?;
+        for &&(old, new, a, b) in &copyright_changes {
+            writeln!(out, "  {}", label(old, new))?;
+            writeln!(out, "    - {}", a.copyright_notice)?;
+            writeln!(out, "    + {}", b.copyright_notice)?;
+        }
+    }
+
+    let text_changes = descriptions.iter()
+        .filter(|x| !same_text(&x.2.full_license_document, &x.3.full_license_document))
+        .collect::<Vec<_>>();
+    if !text_changes.is_empty() {
+        write_heading(out, "License text changes", &mut first)?;
+        for &&(old, new, a, b) in &text_changes {
+            writeln!(out, "  {}", label(old, new))?;
+            write_text_diff(out, &a.full_license_document, &b.full_license_document)?;
+        }
+    }
+
+    if first {
+        writeln!(out, "No license changes")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use license::LicenseId;
+    use output::fixture;
+
+    #[test]
+    fn tells_reports_from_lock_files() {
+        use std::fs;
+        use tempdir::TempDir;
+
+        let dir = TempDir::new("cargo-license-hound-diff").unwrap();
+        let path = |name: &str| dir.path().join(name).to_str().unwrap().to_string();
+
+        fs::write(path("Cargo.lock"), "[[package]]\nname = \"a\"\nversion = \"1.0.0\"\n").unwrap();
+        match Input::read(&path("Cargo.lock")) {
+            Ok(Input::LockFile(_)) => {},
+            _ => panic!("Expected a lock file"),
+        }
+
+        // As saved by earlier versions, without a schema_version
+        fs::write(path("old.json"), r#"[{ "package_name": "a", "version": "1.0.0" }]"#).unwrap();
+        match Input::read(&path("old.json")) {
+            Err(InputError::Report(LoadError::UnsupportedSchemaVersion(None))) => {},
+            _ => panic!("Expected an unsupported report"),
+        }
+
+        fs::write(path("truncated.json"), r#"{ "schema_version": 1, "#).unwrap();
+        match Input::read(&path("truncated.json")) {
+            Err(InputError::Report(LoadError::Deserializing(_))) => {},
+            _ => panic!("Expected an invalid report"),
+        }
+    }
+
+    fn changelog(old: &[LicenseReport], new: &[LicenseReport]) -> String {
+        let mut out = vec![];
+        write(&mut out, &compare(old, new)).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn can_diff_lines() {
+        let lines = diff_lines("a\nb\nc", "a\nc\nd");
+        let lines = lines.iter()
+            .map(|x| match *x {
+                Line::Same(x) => format!(" {}", x),
+                Line::Removed(x) => format!("-{}", x),
+                Line::Added(x) => format!("+{}", x),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![" a", "-b", " c", "+d"], lines);
+    }
+
+    #[test]
+    fn can_describe_changes() {
+        let mit = "Copyright (c) 2017 A\n\nPermission is hereby granted\n\n1\n2\n3\n4\n5\n6";
+
+        let old = vec![
+            fixture::ok("a", "1.0.0", LicenseId::Mit, mit),
+            fixture::ok("b", "0.1.0", LicenseId::Mit, "Copyright (c) 2017 B"),
+            fixture::ok("c", "0.1.0", LicenseId::Mit, "Copyright (c) 2017 C"),
+            fixture::ok("d", "2.0.0", LicenseId::Bsd3Clause, "Copyright (c) 2017 D"),
+        ];
+
+        let mut new = vec![
+            fixture::ok("a", "1.1.0", LicenseId::Mit, &mit.replace("2017 A", "2018 A").replace("6", "6b")),
+            fixture::ok("b", "0.1.0", LicenseId::Mit, "Copyright (c) 2017 B"),
+            fixture::unrecoverable("c"),
+            fixture::ok("e", "0.3.0", LicenseId::Mpl2, "Copyright (c) 2017 E"),
+        ];
+        new[0].conclusion.as_mut().unwrap().full_spdx_license = "MIT/Apache-2.0".to_string();
+        new[2].version = "0.1.0".to_string();
+
+        let expected = [
+            "Added crates",
+            "  + e 0.3.0: MPL-2.0",
+            "",
+            "Removed crates",
+            "  - d 2.0.0: BSD-3-Clause",
+            "",
+            "License changes",
+            "  a 1.0.0 -> 1.1.0: MIT -> MIT OR Apache-2.0 (using MIT)",
+            "",
+            "Status changes",
+            "  c 0.1.0: OK -> ERROR UnableToRecoverLicenseFile",
+            "",
+            "Copyright changes",
+            "  a 1.0.0 -> 1.1.0",
+            "    - Copyright (c) 2017 A",
+            "    + Copyright (c) 2018 A",
+            "",
+            "License text changes",
+            "  a 1.0.0 -> 1.1.0",
+            "    - Copyright (c) 2017 A",
+            "    + Copyright (c) 2018 A",
+            "",
+            "      Permission is hereby granted",
+            "    ...",
+            "      4",
+            "      5",
+            "    - 6",
+            "    + 6b",
+            "",
+        ];
+
+        assert_eq!(expected.join("\n"), changelog(&old, &new));
+    }
+
+    #[test]
+    fn leaves_failures_to_status_changes() {
+        let old = vec![fixture::ok("a", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 A")];
+        let new = vec![fixture::unrecoverable("a")];
+
+        let expected = [
+            "Status changes",
+            "  a 1.0.0: OK -> ERROR UnableToRecoverLicenseFile",
+            "",
+        ];
+
+        assert_eq!(expected.join("\n"), changelog(&old, &new));
+        assert_eq!(expected.join("\n").replace("OK -> ERROR UnableToRecoverLicenseFile", "ERROR UnableToRecoverLicenseFile -> OK"), changelog(&new, &old));
+    }
+
+    #[test]
+    fn says_so_when_nothing_changed() {
+        let reports = vec![fixture::ok("a", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 A")];
+        assert_eq!("No license changes\n", changelog(&reports, &reports));
+    }
+}
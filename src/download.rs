@@ -0,0 +1,204 @@
+//! Downloading crates from registries that require authentication. Cargo
+//! itself only sends registry tokens when publishing, so such crates are
+//! downloaded and unpacked by license-hound, the way Cargo would.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use cargo::core::SourceId;
+use cargo::util::{Config, short_hash};
+use flate2::read::GzDecoder;
+use reqwest;
+use serde_json;
+use tar::Archive;
+
+use http::{self, Client, Request};
+use license::sha256_hex;
+use read_file;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Http(http::Error),
+
+    /// The `config.json` of the registry index is missing or invalid
+    IndexConfig(PathBuf),
+
+    Status(String, reqwest::StatusCode),
+    ChecksumMismatch { expected: String, actual: String },
+}
+
+impl From<io::Error> for Error {
+    fn from(other: io::Error) -> Error {
+        Error::Io(other)
+    }
+}
+
+impl From<http::Error> for Error {
+    fn from(other: http::Error) -> Error {
+        Error::Http(other)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Error::Io(ref err) => write!(f, "{}", err),
+            &Error::Http(ref err) => write!(f, "{:?}", err),
+            &Error::IndexConfig(ref path) => write!(f, "Unable to read the registry configuration {}", path.display()),
+            &Error::Status(ref url, status) => write!(f, "Unexpected status code from {}: {}", url, status),
+            &Error::ChecksumMismatch { ref expected, ref actual } =>
+                write!(f, "The checksum {} does not match the lock file, which has {}", actual, expected),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct IndexConfig {
+    dl: String,
+}
+
+/// The name Cargo gives the directories of a registry, as in
+/// `~/.cargo/registry/index/<name>`
+fn short_name(source_id: &SourceId) -> String {
+    format!("{}-{}", source_id.url().host_str().unwrap_or(""), short_hash(source_id))
+}
+
+/// The URL of the `.crate` file, given the `dl` template of the registry
+/// index. Without any `{crate}` or `{version}` markers, `/{crate}/{version}/download`
+/// is appended.
+fn crate_url(dl: &str, name: &str, version: &str) -> String {
+    if dl.contains("{crate}") || dl.contains("{version}") {
+        dl.replace("{crate}", name).replace("{version}", version)
+    } else {
+        format!("{}/{}/{}/download", dl.trim_end_matches('/'), name, version)
+    }
+}
+
+/// Retrieves the `.crate` file at `url`, checking it against `checksum` from
+/// the lock file when that is known
+fn fetch(client: &dyn Client, url: &str, token: &str, checksum: Option<&str>) -> Result<Vec<u8>, Error> {
+    let mut request = Request::get(url);
+    request.headers.set_raw("Authorization", token.to_string());
+
+    let resp = client.send(&request)?;
+    if !resp.status.is_success() {
+        return Err(Error::Status(url.to_string(), resp.status));
+    }
+
+    if let Some(expected) = checksum {
+        let actual = sha256_hex(&resp.body);
+        if actual != expected {
+            return Err(Error::ChecksumMismatch { expected: expected.to_string(), actual });
+        }
+    }
+
+    Ok(resp.body)
+}
+
+/// Unpacks a `.crate` file into `dir`, where it gets a directory named
+/// `<name>-<version>`
+fn unpack(archive: &[u8], dir: &Path) -> Result<(), Error> {
+    fs::create_dir_all(dir)?;
+    Archive::new(GzDecoder::new(archive)).unpack(dir)?;
+    Ok(())
+}
+
+/// Downloads `name` `version` from the registry at `source_id` with `token`,
+/// giving the directory it was unpacked in. The index must have been
+/// updated. Crates already unpacked by an earlier run are not downloaded again.
+pub fn download(config: &Config, client: &dyn Client, source_id: &SourceId, name: &str, version: &str, token: &str, checksum: Option<&str>) -> Result<PathBuf, Error> {
+    let registry = short_name(source_id);
+    let dir = config.registry_source_path().join(&registry).into_path_unlocked();
+    let package_dir = dir.join(format!("{}-{}", name, version));
+
+    // Cargo marks completely unpacked crates the same way
+    let ok = package_dir.join(".cargo-ok");
+    if ok.exists() {
+        return Ok(package_dir);
+    }
+
+    let index_config = config.registry_index_path().join(&registry).into_path_unlocked().join("config.json");
+    let dl = read_file(&index_config).ok()
+        .and_then(|x| serde_json::from_str::<IndexConfig>(&x).ok())
+        .ok_or_else(|| Error::IndexConfig(index_config.clone()))?
+        .dl;
+
+    let archive = fetch(client, &crate_url(&dl, name, version), token, checksum)?;
+    unpack(&archive, &dir)?;
+    fs::File::create(ok)?;
+
+    Ok(package_dir)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use http::mock::MockClient;
+    use tar::{Builder, Header};
+    use tempdir::TempDir;
+
+    const URL: &str = "https://crates.example.com/api/v1/crates/package/1.0.0/download";
+
+    fn crate_file() -> Vec<u8> {
+        let license = b"Copyright (c) 2017 Someone\n";
+
+        let mut header = Header::new_gnu();
+        header.set_size(license.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+
+        let mut builder = Builder::new(GzEncoder::new(vec![], Compression::default()));
+        builder.append_data(&mut header, "package-1.0.0/LICENSE", &license[..]).unwrap();
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn can_build_crate_urls() {
+        assert_eq!(URL, crate_url("https://crates.example.com/api/v1/crates", "package", "1.0.0"));
+        assert_eq!(URL, crate_url("https://crates.example.com/api/v1/crates/", "package", "1.0.0"));
+        assert_eq!(
+            "https://dl.example.com/package-1.0.0.crate",
+            crate_url("https://dl.example.com/{crate}-{version}.crate", "package", "1.0.0")
+        );
+    }
+
+    #[test]
+    fn sends_token_and_unpacks_crate() {
+        let archive = crate_file();
+        let checksum = sha256_hex(&archive);
+        let client = MockClient::with_bytes(&[(URL, 200, archive)]);
+
+        let archive = fetch(&client, URL, "secret", Some(&checksum)).unwrap();
+
+        let requests = client.requests();
+        let authorization = requests[0].headers.get_raw("Authorization").unwrap().one().unwrap();
+        assert_eq!(b"secret", authorization);
+
+        let dir = TempDir::new("cargo-license-hound-registry").unwrap();
+        unpack(&archive, dir.path()).unwrap();
+        assert_eq!(
+            "Copyright (c) 2017 Someone\n",
+            read_file(dir.path().join("package-1.0.0").join("LICENSE")).unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_unexpected_crate_files() {
+        let client = MockClient::with_bytes(&[(URL, 200, crate_file())]);
+        match fetch(&client, URL, "secret", Some("0123")) {
+            Err(Error::ChecksumMismatch { ref expected, .. }) => assert_eq!("0123", expected),
+            x => panic!("Unexpected result {:?}", x.map(|_| ())),
+        }
+
+        let client = MockClient::new(&[(URL, 401, "")]);
+        match fetch(&client, URL, "wrong", None) {
+            Err(Error::Status(_, status)) => assert_eq!(401, status.as_u16()),
+            x => panic!("Unexpected result {:?}", x.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! SPDX license expressions, as declared in crate manifests, such as
+//! `MIT OR Apache-2.0` or `(MIT AND Zlib) OR GPL-2.0 WITH Classpath-exception-2.0`
+
+use std::fmt;
+use std::iter::Peekable;
+use std::vec;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expression {
+    License { id: String, exception: Option<String> },
+
+    /// All of these must be complied with
+    And(Vec<Expression>),
+
+    /// Any one of these may be chosen
+    Or(Vec<Expression>),
+}
+
+impl Expression {
+    /// The identifiers of all the licenses mentioned
+    pub fn licenses(&self) -> Vec<&str> {
+        match self {
+            &Expression::License { ref id, .. } => vec![id],
+            &Expression::And(ref terms) | &Expression::Or(ref terms) =>
+                terms.iter().flat_map(|x| x.licenses()).collect(),
+        }
+    }
+
+    /// The sets of licenses that may be complied with, any one of which
+    /// satisfies the expression. `(MIT AND Zlib) OR Apache-2.0` has the
+    /// alternatives `[MIT, Zlib]` and `[Apache-2.0]`.
+    pub fn alternatives(&self) -> Vec<Vec<&Expression>> {
+        match self {
+            &Expression::License { .. } => vec![vec![self]],
+            &Expression::Or(ref terms) => terms.iter().flat_map(|x| x.alternatives()).collect(),
+            &Expression::And(ref terms) => terms.iter().fold(vec![vec![]], |combined, term| {
+                let alternatives = term.alternatives();
+                combined.iter()
+                    .flat_map(|x| alternatives.iter().map(move |y| x.iter().chain(y).cloned().collect()))
+                    .collect()
+            }),
+        }
+    }
+}
+
+/// Writes the expression in the canonical form, with operators in upper case
+/// and parentheses only where they are needed
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Expression::License { ref id, exception: Some(ref exception) } => write!(f, "{} WITH {}", id, exception),
+            &Expression::License { ref id, exception: None } => write!(f, "{}", id),
+            &Expression::And(ref terms) => {
+                let terms = terms.iter()
+                    .map(|x| match x {
+                        &Expression::Or(_) => format!("({})", x),
+                        _ => x.to_string(),
+                    })
+                    .collect::<Vec<_>>();
+                write!(f, "{}", terms.join(" AND "))
+            },
+            &Expression::Or(ref terms) =>
+                write!(f, "{}", terms.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(" OR ")),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.replace('(', " ( ")
+        .replace(')', " ) ")
+        .replace('/', " / ")
+        .split_whitespace()
+        .map(|x| x.to_string())
+        .collect()
+}
+
+fn is_operator(token: &str, operator: &str) -> bool {
+    token.eq_ignore_ascii_case(operator)
+}
+
+type Tokens = Peekable<vec::IntoIter<String>>;
+
+fn next_is(tokens: &mut Tokens, operator: &str) -> bool {
+    if tokens.peek().map(|x| is_operator(x, operator)).unwrap_or(false) {
+        tokens.next();
+        true
+    } else {
+        false
+    }
+}
+
+fn parse_or(tokens: &mut Tokens) -> Option<Expression> {
+    let mut terms = vec![try_opt!(parse_and(tokens))];
+    while next_is(tokens, "OR") || next_is(tokens, "/") {
+        terms.push(try_opt!(parse_and(tokens)));
+    }
+
+    Some(if terms.len() == 1 { terms.remove(0) } else { Expression::Or(terms) })
+}
+
+fn parse_and(tokens: &mut Tokens) -> Option<Expression> {
+    let mut terms = vec![try_opt!(parse_license(tokens))];
+    while next_is(tokens, "AND") {
+        terms.push(try_opt!(parse_license(tokens)));
+    }
+
+    Some(if terms.len() == 1 { terms.remove(0) } else { Expression::And(terms) })
+}
+
+fn parse_license(tokens: &mut Tokens) -> Option<Expression> {
+    let token = try_opt!(tokens.next());
+
+    if token == "(" {
+        let expression = try_opt!(parse_or(tokens));
+        return if tokens.next().map(|x| x == ")").unwrap_or(false) { Some(expression) } else { None };
+    }
+
+    if token == ")" || ["/", "AND", "OR", "WITH"].iter().any(|x| is_operator(&token, x)) {
+        return None;
+    }
+
+    let exception = if next_is(tokens, "WITH") { Some(try_opt!(tokens.next())) } else { None };
+
+    Some(Expression::License { id: token, exception })
+}
+
+/// Parses `text`, returning `None` if it is not a valid expression. Operators
+/// are accepted in any case, and the deprecated `MIT/Apache-2.0` syntax is
+/// read as `MIT OR Apache-2.0`.
+pub fn parse(text: &str) -> Option<Expression> {
+    let mut tokens = tokenize(text).into_iter().peekable();
+    let expression = try_opt!(parse_or(&mut tokens));
+
+    if tokens.next().is_some() {
+        return None;
+    }
+
+    Some(expression)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn license(id: &str) -> Expression {
+        Expression::License { id: id.to_string(), exception: None }
+    }
+
+    #[test]
+    fn can_parse_expressions() {
+        assert_eq!(Some(license("MIT")), parse("MIT"));
+        assert_eq!(Some(Expression::Or(vec![license("MIT"), license("Apache-2.0")])), parse("MIT/Apache-2.0"));
+        assert_eq!(
+            Some(Expression::Or(vec![
+                Expression::And(vec![license("MIT"), license("Zlib")]),
+                Expression::License {
+                    id: "GPL-2.0".to_string(),
+                    exception: Some("Classpath-exception-2.0".to_string()),
+                },
+            ])),
+            parse("(MIT and Zlib) OR GPL-2.0 WITH Classpath-exception-2.0")
+        );
+    }
+
+    #[test]
+    fn can_list_alternatives() {
+        let expression = parse("(MIT OR Apache-2.0) AND (Zlib OR ISC)").unwrap();
+        let alternatives = expression.alternatives().into_iter()
+            .map(|x| x.into_iter().cloned().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                vec![license("MIT"), license("Zlib")],
+                vec![license("MIT"), license("ISC")],
+                vec![license("Apache-2.0"), license("Zlib")],
+                vec![license("Apache-2.0"), license("ISC")],
+            ],
+            alternatives
+        );
+    }
+
+    #[test]
+    fn can_write_expressions() {
+        for &(text, expected) in &[
+            ("MIT/Apache-2.0", "MIT OR Apache-2.0"),
+            ("(MIT and Zlib) or GPL-2.0 with Classpath-exception-2.0", "MIT AND Zlib OR GPL-2.0 WITH Classpath-exception-2.0"),
+            ("(MIT OR Apache-2.0) AND Unicode-DFS-2016", "(MIT OR Apache-2.0) AND Unicode-DFS-2016"),
+        ] {
+            assert_eq!(expected, parse(text).unwrap().to_string());
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        assert_eq!(None, parse(""));
+        assert_eq!(None, parse("MIT OR"));
+        assert_eq!(None, parse("(MIT"));
+        assert_eq!(None, parse("MIT Apache-2.0"));
+    }
+}
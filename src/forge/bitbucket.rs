@@ -0,0 +1,110 @@
+use regex::Regex;
+
+use super::*;
+
+#[derive(Deserialize)]
+struct Branch {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Repository {
+    mainbranch: Option<Branch>,
+}
+
+pub struct Bitbucket {
+    web_url: String,
+    api_url: String,
+    url_pattern: Regex,
+}
+
+impl Bitbucket {
+    pub fn new(web_url: &str, api_url: &str) -> Bitbucket {
+        Bitbucket {
+            web_url: web_url.trim_end_matches('/').to_string(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+            url_pattern: url_pattern(web_url, "[^/]+", "src"),
+        }
+    }
+}
+
+impl Default for Bitbucket {
+    fn default() -> Bitbucket {
+        Bitbucket::new("https://bitbucket.org", "https://api.bitbucket.org/2.0")
+    }
+}
+
+impl Forge for Bitbucket {
+    fn name(&self) -> &'static str {
+        "bitbucket"
+    }
+
+    fn serves(&self, url: &str) -> bool {
+        url.starts_with(&format!("{}/", self.web_url))
+    }
+
+    fn parse_url(&self, url: &str) -> Option<RepoLocation> {
+        self.url_pattern.captures(url).map(RepoLocation::from_captures)
+    }
+
+    fn default_branch(&self, connection: &Connection, location: &RepoLocation) -> Option<String> {
+        let url = format!("{}/repositories/{}/{}", self.api_url, location.owner, location.repo);
+
+        let repository: Repository = try_opt!(fetch_json(self.name(), connection, &self.request(&url)));
+
+        repository.mainbranch.map(|x| x.name)
+    }
+
+    fn raw_file(&self, connection: &Connection, location: &RepoLocation, git_ref: &str, path: &str) -> Option<RemoteFile> {
+        let url = format!("{}/{}/{}/raw/{}/{}", self.web_url, location.owner, location.repo, git_ref, path);
+        fetch_text(self.name(), connection, &self.request(&url))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use http::mock::MockClient;
+
+    #[test]
+    fn can_parse_repository_urls() {
+        assert_eq!(
+            Some(RepoLocation {
+                owner: "owner".to_string(),
+                repo: "repo".to_string(),
+                git_ref: Some("default".to_string()),
+                path: "package".to_string(),
+            }),
+            Bitbucket::default().parse_url("https://bitbucket.org/owner/repo/src/default/package/")
+        );
+    }
+
+    #[test]
+    fn finds_license_file() {
+        let client = MockClient::new(&[
+            ("https://bitbucket.example.com/api/repositories/owner/repo", 200, r#"{ "mainbranch": { "name": "default", "type": "branch" } }"#),
+            ("https://bitbucket.example.com/owner/repo/raw/default/LICENSE-MIT", 200, "Copyright (c) 2017 Someone"),
+        ]);
+
+        let forges = Forges::new(vec![Box::new(Bitbucket::new("https://bitbucket.example.com", "https://bitbucket.example.com/api"))], Rc::new(client));
+
+        let (source, license) = forges.license_file_from_repository(
+            Some("https://bitbucket.example.com/owner/repo"),
+            None,
+            None,
+            "package",
+            "MIT",
+            LicenseId::Mit,
+            &mut vec![],
+        ).unwrap();
+
+        assert_eq!("Copyright (c) 2017 Someone", license.contents);
+        match source {
+            LicenseOrigin::ForgeRepo { forge, git_ref, .. } => {
+                assert_eq!("bitbucket", forge);
+                assert_eq!("default", git_ref);
+            },
+            x => panic!("Unexpected license source {:?}", x),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Tokens stored by the GitHub CLI, `gh`, in its `hosts.yml`
+
+use std::env;
+use std::path::PathBuf;
+
+use read_file;
+
+fn hosts_file() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("GH_CONFIG_DIR") {
+        return Some(PathBuf::from(dir).join("hosts.yml"));
+    }
+
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(dir).join("gh").join("hosts.yml"));
+    }
+
+    if cfg!(windows) {
+        if let Some(dir) = env::var_os("AppData") {
+            return Some(PathBuf::from(dir).join("GitHub CLI").join("hosts.yml"));
+        }
+    }
+
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".config").join("gh").join("hosts.yml"))
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim_matches(|c| c == '"' || c == '\'')
+}
+
+/// Finds the `oauth_token` for `host` in the contents of a `hosts.yml`.
+///
+/// This is not a YAML parser. It only understands the simple structure
+/// `gh` writes, with one unindented key per host.
+fn find_token(hosts_yml: &str, host: &str) -> Option<String> {
+    let mut in_host = false;
+
+    for line in hosts_yml.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        if !line.starts_with(' ') && !line.starts_with('\t') {
+            in_host = unquote(line.trim_end().trim_end_matches(':')) == host;
+            continue;
+        }
+
+        let line = line.trim();
+        if in_host && line.starts_with("oauth_token:") {
+            let token = unquote(line["oauth_token:".len()..].trim());
+            if !token.is_empty() {
+                return Some(token.to_string());
+            }
+        }
+    }
+
+    None
+}
+
+pub fn token_for_host(host: &str) -> Option<String> {
+    let hosts_yml = try_opt!(read_file(try_opt!(hosts_file())).ok());
+    find_token(&hosts_yml, host)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const HOSTS_YML: &str = "\
+github.com:
+    user: octocat
+    oauth_token: gho_public
+    git_protocol: https
+\"github.example.com\":
+    users:
+        octocat:
+            oauth_token: 'gho_enterprise'
+    user: octocat
+";
+
+    #[test]
+    fn can_find_tokens() {
+        assert_eq!(Some("gho_public".to_string()), find_token(HOSTS_YML, "github.com"));
+        assert_eq!(Some("gho_enterprise".to_string()), find_token(HOSTS_YML, "github.example.com"));
+        assert_eq!(None, find_token(HOSTS_YML, "example.com"));
+    }
+}
@@ -0,0 +1,159 @@
+use regex::Regex;
+
+use config::ForgeConfig;
+use super::*;
+
+#[derive(Deserialize)]
+struct Repository {
+    default_branch: String,
+}
+
+/// Gitea and its fork Forgejo, which powers Codeberg
+pub struct Gitea {
+    web_url: String,
+    url_pattern: Regex,
+    token: Option<String>,
+}
+
+impl Gitea {
+    pub fn new(web_url: &str) -> Gitea {
+        Gitea {
+            web_url: web_url.trim_end_matches('/').to_string(),
+            url_pattern: url_pattern(web_url, "[^/]+", "src/(?:branch|tag|commit)"),
+            token: None,
+        }
+    }
+
+    /// A self-hosted instance from a `[[gitea]]` section of the
+    /// configuration file
+    pub fn from_config(config: &ForgeConfig) -> Gitea {
+        Gitea {
+            token: config.token.clone(),
+            ..Gitea::new(&config.web_url)
+        }
+    }
+}
+
+impl Default for Gitea {
+    fn default() -> Gitea {
+        Gitea::new("https://codeberg.org")
+    }
+}
+
+impl Forge for Gitea {
+    fn name(&self) -> &'static str {
+        "gitea"
+    }
+
+    fn serves(&self, url: &str) -> bool {
+        url.starts_with(&format!("{}/", self.web_url))
+    }
+
+    fn request(&self, url: &str) -> Request {
+        let mut request = Request::get(url);
+        if let Some(ref token) = self.token {
+            request.headers.set_raw("Authorization", format!("token {}", token));
+        }
+        request
+    }
+
+    fn parse_url(&self, url: &str) -> Option<RepoLocation> {
+        self.url_pattern.captures(url).map(RepoLocation::from_captures)
+    }
+
+    fn default_branch(&self, connection: &Connection, location: &RepoLocation) -> Option<String> {
+        let url = format!("{}/api/v1/repos/{}/{}", self.web_url, location.owner, location.repo);
+
+        let repository: Repository = try_opt!(fetch_json(self.name(), connection, &self.request(&url)));
+
+        Some(repository.default_branch)
+    }
+
+    fn raw_file(&self, connection: &Connection, location: &RepoLocation, git_ref: &str, path: &str) -> Option<RemoteFile> {
+        // This form of raw URL accepts branches, tags and commits alike
+        let url = format!("{}/{}/{}/raw/{}/{}", self.web_url, location.owner, location.repo, git_ref, path);
+        fetch_text(self.name(), connection, &self.request(&url))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use http::mock::MockClient;
+
+    #[test]
+    fn can_parse_repository_urls() {
+        let gitea = Gitea::default();
+
+        assert_eq!(
+            Some(RepoLocation {
+                owner: "owner".to_string(),
+                repo: "repo".to_string(),
+                git_ref: None,
+                path: "".to_string(),
+            }),
+            gitea.parse_url("https://codeberg.org/owner/repo")
+        );
+
+        assert_eq!(
+            Some(RepoLocation {
+                owner: "owner".to_string(),
+                repo: "repo".to_string(),
+                git_ref: Some("main".to_string()),
+                path: "crates/package".to_string(),
+            }),
+            gitea.parse_url("https://codeberg.org/owner/repo/src/branch/main/crates/package")
+        );
+    }
+
+    #[test]
+    fn finds_license_file() {
+        let client = MockClient::new(&[
+            ("https://gitea.example.com/api/v1/repos/owner/repo", 200, r#"{ "default_branch": "main" }"#),
+            ("https://gitea.example.com/owner/repo/raw/main/LICENSE.md", 200, RAW_LICENSE),
+        ]);
+
+        let forges = Forges::new(vec![Box::new(Gitea::new("https://gitea.example.com"))], Rc::new(client));
+
+        let (source, license) = forges.license_file_from_repository(
+            Some("https://gitea.example.com/owner/repo"),
+            None,
+            None,
+            "package",
+            "MIT",
+            LicenseId::Bsd3Clause,
+            &mut vec![],
+        ).unwrap();
+
+        assert_eq!(RAW_LICENSE, license.contents);
+        match source {
+            LicenseOrigin::ForgeRepo { forge, git_ref, .. } => {
+                assert_eq!("gitea", forge);
+                assert_eq!("main", git_ref);
+            },
+            x => panic!("Unexpected license source {:?}", x),
+        }
+    }
+
+    #[test]
+    fn sends_configured_token() {
+        let client = MockClient::new(&[]);
+        let gitea = Gitea::from_config(&ForgeConfig {
+            web_url: "https://gitea.example.com".to_string(),
+            token: Some("secret".to_string()),
+        });
+
+        gitea.raw_file(&Connection { client: &client, limiter: &RateLimiter::default() }, &RepoLocation {
+            owner: "owner".to_string(),
+            repo: "repo".to_string(),
+            git_ref: None,
+            path: "".to_string(),
+        }, "main", "LICENSE");
+
+        let requests = client.requests();
+        assert_eq!("https://gitea.example.com/owner/repo/raw/main/LICENSE", requests[0].url);
+        assert_eq!(b"token secret", requests[0].headers.get_raw("Authorization").unwrap().one().unwrap());
+    }
+
+    const RAW_LICENSE: &str = "Copyright (c) 2017, Someone\nAll rights reserved.\n";
+}
@@ -0,0 +1,688 @@
+use std::fmt;
+
+use base64;
+use regex::Regex;
+use reqwest;
+use serde_json;
+
+use config::GitHubConfig;
+use expression;
+use license::*;
+use super::*;
+use super::gh_hosts;
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+enum Encoding {
+    #[serde(rename = "base64")]
+    Base64,
+}
+
+impl Encoding {
+    fn decode(&self, input: &str) -> Result<String, ()> {
+        match self {
+            &Encoding::Base64 =>
+                base64::decode_config(input, base64::MIME).map_err(|_| ())
+                    .and_then(|x| String::from_utf8(x).map_err(|_| ())),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct LicenseDescriptor {
+    spdx_id: String,
+}
+
+#[derive(Deserialize)]
+struct LicenseDocument {
+    download_url: String,
+    content: String,
+    encoding: Encoding,
+    license: LicenseDescriptor,
+}
+
+#[derive(Deserialize)]
+struct Repository {
+    default_branch: String,
+}
+
+#[derive(Deserialize)]
+struct GitHubError {
+    message: String,
+    documentation_url: Option<String>,
+}
+
+impl fmt::Display for GitHubError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.message)?;
+
+        if let Some(ref documentation_url) = self.documentation_url {
+            write!(f, " ({})", documentation_url)?;
+        };
+
+        Ok(())
+    }
+}
+
+const LICENSE_HOUND_GITHUB_USERNAME: &str = "LICENSE_HOUND_GITHUB_USERNAME";
+const LICENSE_HOUND_GITHUB_PASSWORD: &str = "LICENSE_HOUND_GITHUB_PASSWORD";
+
+const GITHUB_TOKEN: &str = "GITHUB_TOKEN";
+const GH_ENTERPRISE_TOKEN: &str = "GH_ENTERPRISE_TOKEN";
+
+const PUBLIC_HOST: &str = "github.com";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Auth {
+    Anonymous,
+    Token(String),
+
+    /// Username and password, as given by `LICENSE_HOUND_GITHUB_USERNAME`
+    /// and `LICENSE_HOUND_GITHUB_PASSWORD`. GitHub no longer accepts
+    /// passwords, but a personal access token works in place of one.
+    Basic(String, Option<String>),
+}
+
+impl Auth {
+    /// Looks for credentials for the GitHub instance at `host`. A token is
+    /// taken from the environment, the configuration file or the hosts file
+    /// of the GitHub CLI, in that order.
+    pub fn discover(host: &str, configured_token: Option<&str>) -> Auth {
+        use std::env::var;
+
+        let token_variable = if host == PUBLIC_HOST { GITHUB_TOKEN } else { GH_ENTERPRISE_TOKEN };
+
+        let token = var(token_variable).ok()
+            .or_else(|| configured_token.map(|x| x.to_string()))
+            .or_else(|| gh_hosts::token_for_host(host));
+
+        if let Some(token) = token {
+            return Auth::Token(token);
+        }
+
+        if let (Ok(username), password) = (var(LICENSE_HOUND_GITHUB_USERNAME), var(LICENSE_HOUND_GITHUB_PASSWORD).ok()) {
+            return Auth::Basic(username, password);
+        }
+
+        Auth::Anonymous
+    }
+}
+
+fn try_to_print_error(resp: &Response) {
+    if let Ok(err) = serde_json::from_slice::<GitHubError>(&resp.body) {
+        eprintln!("ERROR github: {}", err);
+    }
+}
+
+fn report_forbidden(url: &str, resp: &Response) {
+    eprintln!("ERROR Request to {} forbidden by GitHub", url);
+    try_to_print_error(resp);
+    eprintln!("HINT Try authenticating with a GitHub token:");
+    eprintln!("HINT     {}=... cargo license-hound", GITHUB_TOKEN);
+}
+
+pub struct GitHub {
+    api_url: String,
+    raw_url: String,
+    url_pattern: Regex,
+    auth: Auth,
+}
+
+impl GitHub {
+    /// `web_url` is the base of repository URLs as given in crate
+    /// manifests, `api_url` is the base of the REST API and `raw_url` the
+    /// base of URLs for raw file contents.
+    pub fn new(web_url: &str, api_url: &str, raw_url: &str, auth: Auth) -> GitHub {
+        GitHub {
+            api_url: api_url.trim_end_matches('/').to_string(),
+            raw_url: raw_url.trim_end_matches('/').to_string(),
+            url_pattern: url_pattern(web_url, "[^/]+", "(?:tree|blob)"),
+            auth,
+        }
+    }
+
+    /// github.com, with any URLs overridden by the `[github]` section of
+    /// the configuration file
+    pub fn public(config: &GitHubConfig) -> GitHub {
+        let web_url = config.web_url.as_ref().map(|x| &**x).unwrap_or("https://github.com");
+
+        GitHub::new(
+            web_url,
+            config.api_url.as_ref().map(|x| &**x).unwrap_or("https://api.github.com"),
+            config.raw_url.as_ref().map(|x| &**x).unwrap_or("https://raw.githubusercontent.com"),
+            Auth::discover(PUBLIC_HOST, config.token.as_ref().map(|x| &**x)),
+        )
+    }
+
+    /// A GitHub Enterprise instance. Unless configured otherwise, its REST
+    /// API and raw files are served from below `web-url`, as they are for
+    /// instances without subdomain isolation.
+    pub fn enterprise(config: &GitHubConfig) -> Option<GitHub> {
+        let web_url = try_opt!(config.web_url.as_ref()).trim_end_matches('/');
+
+        Some(GitHub::new(
+            web_url,
+            &config.api_url.clone().unwrap_or_else(|| format!("{}/api/v3", web_url)),
+            &config.raw_url.clone().unwrap_or_else(|| format!("{}/raw", web_url)),
+            Auth::discover(host_of(web_url), config.token.as_ref().map(|x| &**x)),
+        ))
+    }
+
+    fn get_license_file(&self, connection: &Connection, url: &str) -> Option<RemoteFile> {
+        let resp = try_opt!(connection.send(&self.request(url)));
+
+        if resp.status == reqwest::StatusCode::Forbidden {
+            report_forbidden(url, &resp);
+            return None;
+        }
+
+        if resp.status.is_success() {
+            return RemoteFile::from_response(url, &resp);
+        }
+
+        None
+    }
+
+    fn license_file_from_license_api(&self, connection: &Connection, owner: &str, repo: &str, git_ref: Option<&str>, package_name: &str, declared_license: &str, chosen_license: LicenseId, findings: &mut Vec<Finding>) -> Option<(LicenseOrigin, RemoteFile)> {
+        let license_url = match git_ref {
+            Some(git_ref) => format!("{}/repos/{}/{}/license?ref={}", self.api_url, owner, repo, git_ref),
+            None => format!("{}/repos/{}/{}/license", self.api_url, owner, repo),
+        };
+
+        let resp = try_opt!(connection.send(&self.request(&license_url)));
+
+        if resp.status == reqwest::StatusCode::Forbidden {
+            report_forbidden(&license_url, &resp);
+            return None;
+        }
+
+        if resp.status == reqwest::StatusCode::NotFound {
+            return None;
+        }
+
+        if !resp.status.is_success() {
+            eprintln!("ERROR Unexpected status code from GitHub API ({}): {}", license_url, resp.status);
+            try_to_print_error(&resp);
+            return None;
+        }
+
+        let license_description: LicenseDocument = try_opt!(serde_json::from_slice(&resp.body).ok());
+
+        let contents = try_opt!(license_description.encoding.decode(&license_description.content).ok());
+
+        let detected_license = &license_description.license.spdx_id;
+
+        // GitHub found a license file, but could not tell which license it is
+        if detected_license == "NOASSERTION" || detected_license == "other" {
+            return None;
+        }
+
+        // Another license the crate may be used under, rather than the one
+        // license-hound is looking for. Not a disagreement, but not the
+        // license file we want either.
+        let is_declared = expression::parse(declared_license)
+            .map(|x| x.licenses().iter().any(|id| same_license(id, detected_license)))
+            .unwrap_or(false);
+        let is_chosen = same_license(chosen_license.spdx_id(), detected_license);
+        if is_declared && !is_chosen {
+            return None;
+        }
+
+        if !is_chosen {
+            eprintln!(
+                "WARN GitHub and license-hound have identified different licenses \
+                for package {:?}: {:?} and {:?}, respectively",
+                package_name,
+                license_description.license.spdx_id,
+                chosen_license.spdx_id(),
+            );
+
+            // Keep what GitHub found for review, but keep looking for a
+            // license file matching the manifest
+            findings.push(Finding::LicenseMismatch {
+                service: "github".to_string(),
+                url: license_description.download_url,
+                detected_license: license_description.license.spdx_id,
+                chosen_license,
+                license_text: contents,
+            });
+            return None;
+        }
+
+        Some((
+            LicenseOrigin::GitHubApi {
+                url: license_description.download_url.clone(),
+            },
+            RemoteFile {
+                url: license_description.download_url,
+                contents,
+                etag: None,
+            },
+        ))
+    }
+}
+
+impl Default for GitHub {
+    fn default() -> GitHub {
+        GitHub::public(&GitHubConfig::default())
+    }
+}
+
+impl Forge for GitHub {
+    fn name(&self) -> &'static str {
+        "github"
+    }
+
+    fn serves(&self, url: &str) -> bool {
+        url.starts_with(&format!("{}/", self.raw_url))
+    }
+
+    fn request(&self, url: &str) -> Request {
+        let mut request = Request::get(url);
+
+        match self.auth {
+            Auth::Anonymous => {},
+            Auth::Token(ref token) => {
+                request.header(reqwest::header::Authorization(reqwest::header::Bearer { token: token.clone() }));
+            },
+            Auth::Basic(ref username, ref password) => {
+                request.header(reqwest::header::Authorization(reqwest::header::Basic {
+                    username: username.clone(),
+                    password: password.clone(),
+                }));
+            },
+        }
+
+        request
+    }
+
+    fn parse_url(&self, url: &str) -> Option<RepoLocation> {
+        self.url_pattern.captures(url).map(RepoLocation::from_captures)
+    }
+
+    fn default_branch(&self, connection: &Connection, location: &RepoLocation) -> Option<String> {
+        let url = format!("{}/repos/{}/{}", self.api_url, location.owner, location.repo);
+
+        let resp = try_opt!(connection.send(&self.request(&url)));
+
+        if resp.status == reqwest::StatusCode::Forbidden {
+            report_forbidden(&url, &resp);
+            return None;
+        }
+
+        if !resp.status.is_success() {
+            return None;
+        }
+
+        let repository: Repository = try_opt!(serde_json::from_slice(&resp.body).ok());
+
+        Some(repository.default_branch)
+    }
+
+    fn raw_file(&self, connection: &Connection, location: &RepoLocation, git_ref: &str, path: &str) -> Option<RemoteFile> {
+        let url = format!("{}/{}/{}/{}/{}", self.raw_url, location.owner, location.repo, git_ref, path);
+        self.get_license_file(connection, &url)
+    }
+
+    fn license_file_from_api(&self, connection: &Connection, location: &RepoLocation, git_ref: Option<&str>, package_name: &str, declared_license: &str, chosen_license: LicenseId, findings: &mut Vec<Finding>) -> Option<(LicenseOrigin, RemoteFile)> {
+        self.license_file_from_license_api(connection, &location.owner, &location.repo, git_ref, package_name, declared_license, chosen_license, findings)
+    }
+
+    fn license_origin(&self, url: String, git_ref: &str) -> LicenseOrigin {
+        LicenseOrigin::GitHubRepo { url, git_ref: git_ref.to_string() }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use http::ReqwestClient;
+    use http::mock::MockClient;
+
+    const EXAMPLE_RESPONSE: &[u8] = br#"
+{
+  "name": "LICENSE",
+  "path": "LICENSE",
+  "sha": "401c59dcc4570b954dd6d345e76199e1f4e76266",
+  "size": 1077,
+  "url": "https://api.github.com/repos/benbalter/gman/contents/LICENSE?ref=master",
+  "html_url": "https://github.com/benbalter/gman/blob/master/LICENSE",
+  "git_url": "https://api.github.com/repos/benbalter/gman/git/blobs/401c59dcc4570b954dd6d345e76199e1f4e76266",
+  "download_url": "https://raw.githubusercontent.com/benbalter/gman/master/LICENSE?lab=true",
+  "type": "file",
+  "content": "VGhlIE1JVCBMaWNlbnNlIChNSVQpCgpDb3B5cmlnaHQgKGMpIDIwMTMgQmVu\nIEJhbHRlcgoKUGVybWlzc2lvbiBpcyBoZXJlYnkgZ3JhbnRlZCwgZnJlZSBv\nZiBjaGFyZ2UsIHRvIGFueSBwZXJzb24gb2J0YWluaW5nIGEgY29weSBvZgp0\naGlzIHNvZnR3YXJlIGFuZCBhc3NvY2lhdGVkIGRvY3VtZW50YXRpb24gZmls\nZXMgKHRoZSAiU29mdHdhcmUiKSwgdG8gZGVhbCBpbgp0aGUgU29mdHdhcmUg\nd2l0aG91dCByZXN0cmljdGlvbiwgaW5jbHVkaW5nIHdpdGhvdXQgbGltaXRh\ndGlvbiB0aGUgcmlnaHRzIHRvCnVzZSwgY29weSwgbW9kaWZ5LCBtZXJnZSwg\ncHVibGlzaCwgZGlzdHJpYnV0ZSwgc3VibGljZW5zZSwgYW5kL29yIHNlbGwg\nY29waWVzIG9mCnRoZSBTb2Z0d2FyZSwgYW5kIHRvIHBlcm1pdCBwZXJzb25z\nIHRvIHdob20gdGhlIFNvZnR3YXJlIGlzIGZ1cm5pc2hlZCB0byBkbyBzbywK\nc3ViamVjdCB0byB0aGUgZm9sbG93aW5nIGNvbmRpdGlvbnM6CgpUaGUgYWJv\ndmUgY29weXJpZ2h0IG5vdGljZSBhbmQgdGhpcyBwZXJtaXNzaW9uIG5vdGlj\nZSBzaGFsbCBiZSBpbmNsdWRlZCBpbiBhbGwKY29waWVzIG9yIHN1YnN0YW50\naWFsIHBvcnRpb25zIG9mIHRoZSBTb2Z0d2FyZS4KClRIRSBTT0ZUV0FSRSBJ\nUyBQUk9WSURFRCAiQVMgSVMiLCBXSVRIT1VUIFdBUlJBTlRZIE9GIEFOWSBL\nSU5ELCBFWFBSRVNTIE9SCklNUExJRUQsIElOQ0xVRElORyBCVVQgTk9UIExJ\nTUlURUQgVE8gVEhFIFdBUlJBTlRJRVMgT0YgTUVSQ0hBTlRBQklMSVRZLCBG\nSVRORVNTCkZPUiBBIFBBUlRJQ1VMQVIgUFVSUE9TRSBBTkQgTk9OSU5GUklO\nR0VNRU5ULiBJTiBOTyBFVkVOVCBTSEFMTCBUSEUgQVVUSE9SUyBPUgpDT1BZ\nUklHSFQgSE9MREVSUyBCRSBMSUFCTEUgRk9SIEFOWSBDTEFJTSwgREFNQUdF\nUyBPUiBPVEhFUiBMSUFCSUxJVFksIFdIRVRIRVIKSU4gQU4gQUNUSU9OIE9G\nIENPTlRSQUNULCBUT1JUIE9SIE9USEVSV0lTRSwgQVJJU0lORyBGUk9NLCBP\nVVQgT0YgT1IgSU4KQ09OTkVDVElPTiBXSVRIIFRIRSBTT0ZUV0FSRSBPUiBU\nSEUgVVNFIE9SIE9USEVSIERFQUxJTkdTIElOIFRIRSBTT0ZUV0FSRS4K\n",
+  "encoding": "base64",
+  "_links": {
+    "self": "https://api.github.com/repos/benbalter/gman/contents/LICENSE?ref=master",
+    "git": "https://api.github.com/repos/benbalter/gman/git/blobs/401c59dcc4570b954dd6d345e76199e1f4e76266",
+    "html": "https://github.com/benbalter/gman/blob/master/LICENSE"
+  },
+  "license": {
+    "key": "mit",
+    "name": "MIT License",
+    "spdx_id": "MIT",
+    "url": "https://api.github.com/licenses/mit",
+    "featured": true
+  }
+}
+"#;
+
+    const BASE64_MIT: &str = "VGhlIE1JVCBMaWNlbnNlIChNSVQpCgpDb3B5cmlnaHQgKGMpIDIwMTMgQmVu\nIEJhbHRlcgoKUGVybWlzc2lvbiBpcyBoZXJlYnkgZ3JhbnRlZCwgZnJlZSBv\nZiBjaGFyZ2UsIHRvIGFueSBwZXJzb24gb2J0YWluaW5nIGEgY29weSBvZgp0\naGlzIHNvZnR3YXJlIGFuZCBhc3NvY2lhdGVkIGRvY3VtZW50YXRpb24gZmls\nZXMgKHRoZSAiU29mdHdhcmUiKSwgdG8gZGVhbCBpbgp0aGUgU29mdHdhcmUg\nd2l0aG91dCByZXN0cmljdGlvbiwgaW5jbHVkaW5nIHdpdGhvdXQgbGltaXRh\ndGlvbiB0aGUgcmlnaHRzIHRvCnVzZSwgY29weSwgbW9kaWZ5LCBtZXJnZSwg\ncHVibGlzaCwgZGlzdHJpYnV0ZSwgc3VibGljZW5zZSwgYW5kL29yIHNlbGwg\nY29waWVzIG9mCnRoZSBTb2Z0d2FyZSwgYW5kIHRvIHBlcm1pdCBwZXJzb25z\nIHRvIHdob20gdGhlIFNvZnR3YXJlIGlzIGZ1cm5pc2hlZCB0byBkbyBzbywK\nc3ViamVjdCB0byB0aGUgZm9sbG93aW5nIGNvbmRpdGlvbnM6CgpUaGUgYWJv\ndmUgY29weXJpZ2h0IG5vdGljZSBhbmQgdGhpcyBwZXJtaXNzaW9uIG5vdGlj\nZSBzaGFsbCBiZSBpbmNsdWRlZCBpbiBhbGwKY29waWVzIG9yIHN1YnN0YW50\naWFsIHBvcnRpb25zIG9mIHRoZSBTb2Z0d2FyZS4KClRIRSBTT0ZUV0FSRSBJ\nUyBQUk9WSURFRCAiQVMgSVMiLCBXSVRIT1VUIFdBUlJBTlRZIE9GIEFOWSBL\nSU5ELCBFWFBSRVNTIE9SCklNUExJRUQsIElOQ0xVRElORyBCVVQgTk9UIExJ\nTUlURUQgVE8gVEhFIFdBUlJBTlRJRVMgT0YgTUVSQ0hBTlRBQklMSVRZLCBG\nSVRORVNTCkZPUiBBIFBBUlRJQ1VMQVIgUFVSUE9TRSBBTkQgTk9OSU5GUklO\nR0VNRU5ULiBJTiBOTyBFVkVOVCBTSEFMTCBUSEUgQVVUSE9SUyBPUgpDT1BZ\nUklHSFQgSE9MREVSUyBCRSBMSUFCTEUgRk9SIEFOWSBDTEFJTSwgREFNQUdF\nUyBPUiBPVEhFUiBMSUFCSUxJVFksIFdIRVRIRVIKSU4gQU4gQUNUSU9OIE9G\nIENPTlRSQUNULCBUT1JUIE9SIE9USEVSV0lTRSwgQVJJU0lORyBGUk9NLCBP\nVVQgT0YgT1IgSU4KQ09OTkVDVElPTiBXSVRIIFRIRSBTT0ZUV0FSRSBPUiBU\nSEUgVVNFIE9SIE9USEVSIERFQUxJTkdTIElOIFRIRSBTT0ZUV0FSRS4K\n";
+
+    const RAW_MIT: &str = "The MIT License (MIT)\n\nCopyright (c) 2013 Ben Balter\n\nPermission is hereby granted, free of charge, to any person obtaining a copy of\nthis software and associated documentation files (the \"Software\"), to deal in\nthe Software without restriction, including without limitation the rights to\nuse, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of\nthe Software, and to permit persons to whom the Software is furnished to do so,\nsubject to the following conditions:\n\nThe above copyright notice and this permission notice shall be included in all\ncopies or substantial portions of the Software.\n\nTHE SOFTWARE IS PROVIDED \"AS IS\", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR\nIMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS\nFOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR\nCOPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER\nIN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN\nCONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.\n";
+
+    #[test]
+    fn can_deserialize_response_example() {
+        let x: LicenseDocument = serde_json::from_slice(EXAMPLE_RESPONSE).unwrap();
+        assert_eq!("https://raw.githubusercontent.com/benbalter/gman/master/LICENSE?lab=true", x.download_url);
+        assert_eq!(BASE64_MIT, x.content);
+        assert_eq!(Encoding::Base64, x.encoding);
+        assert_eq!("MIT", x.license.spdx_id);
+    }
+
+    #[test]
+    fn can_decode_base64() {
+        assert_eq!(Ok(RAW_MIT), Encoding::Base64.decode(BASE64_MIT).as_ref().map(|x| &**x));
+    }
+
+    #[test]
+    fn can_parse_repository_urls() {
+        let github = GitHub::default();
+
+        let expected = RepoLocation {
+            owner: "maghoff".to_string(),
+            repo: "cargo-license-hound".to_string(),
+            git_ref: None,
+            path: "".to_string(),
+        };
+
+        assert_eq!(Some(&expected), github.parse_url("https://github.com/maghoff/cargo-license-hound").as_ref());
+        assert_eq!(Some(&expected), github.parse_url("https://github.com/maghoff/cargo-license-hound/").as_ref());
+        assert_eq!(Some(&expected), github.parse_url("https://github.com/maghoff/cargo-license-hound.git").as_ref());
+        assert_eq!(None, github.parse_url("https://github.com/maghoff/cargo-license-hound/issues"));
+        assert_eq!(None, github.parse_url("https://gitlab.com/maghoff/cargo-license-hound"));
+    }
+
+    #[test]
+    fn can_parse_repository_urls_with_subdirectory() {
+        let github = GitHub::default();
+
+        assert_eq!(
+            Some(RepoLocation {
+                owner: "alexcrichton".to_string(),
+                repo: "futures-rs".to_string(),
+                git_ref: Some("master".to_string()),
+                path: "futures-cpupool".to_string(),
+            }),
+            github.parse_url("https://github.com/alexcrichton/futures-rs/tree/master/futures-cpupool")
+        );
+
+        assert_eq!(
+            Some(RepoLocation {
+                owner: "tokio-rs".to_string(),
+                repo: "tokio".to_string(),
+                git_ref: Some("v0.1.x".to_string()),
+                path: "tokio-io/src".to_string(),
+            }),
+            github.parse_url("https://github.com/tokio-rs/tokio/blob/v0.1.x/tokio-io/src/")
+        );
+    }
+
+    #[test]
+    fn can_configure_enterprise_instance() {
+        let config = GitHubConfig {
+            web_url: Some("https://github.example.com/".to_string()),
+            token: Some("token".to_string()),
+            ..GitHubConfig::default()
+        };
+
+        let github = GitHub::enterprise(&config).unwrap();
+
+        assert_eq!("https://github.example.com/api/v3", github.api_url);
+        assert_eq!("https://github.example.com/raw", github.raw_url);
+        assert!(github.parse_url("https://github.example.com/owner/repo").is_some());
+        assert!(github.parse_url("https://github.com/owner/repo").is_none());
+
+        assert!(GitHub::enterprise(&GitHubConfig::default()).is_none());
+    }
+
+    #[test]
+    fn finds_license_file_on_default_branch() {
+        let (report, _, client) = hound_with(MockClient::new(&[
+            ("https://api.github.com/repos/owner/repo", 200, r#"{ "default_branch": "trunk" }"#),
+            ("https://raw.githubusercontent.com/owner/repo/trunk/LICENSE", 200, RAW_MIT),
+        ]));
+
+        let (source, license) = report.unwrap();
+        assert_eq!(RAW_MIT, license.contents);
+        match source {
+            LicenseOrigin::GitHubRepo { url, git_ref } => {
+                assert_eq!("https://raw.githubusercontent.com/owner/repo/trunk/LICENSE", url);
+                assert_eq!("trunk", git_ref);
+            },
+            x => panic!("Unexpected license source {:?}", x),
+        }
+
+        assert!(client.requested_urls().contains(&LICENSE_API_URL.to_string()));
+    }
+
+    #[test]
+    fn searches_crate_subdirectory_before_repository_root() {
+        let client = MockClient::new(&[
+            ("https://raw.githubusercontent.com/owner/repo/0123abc/LICENSE", 200, "Root license"),
+            ("https://raw.githubusercontent.com/owner/repo/0123abc/crates/package/LICENSE", 200, RAW_MIT),
+        ]);
+
+        let forges = Forges::new(vec![Box::new(GitHub::new(
+            "https://github.com",
+            "https://api.github.com",
+            "https://raw.githubusercontent.com",
+            Auth::Anonymous,
+        ))], Rc::new(client));
+
+        let (_, license) = forges.license_file_from_repository(
+            Some("https://github.com/owner/repo/tree/0123abc/crates/package"),
+            None,
+            None,
+            "package",
+            "MIT",
+            LicenseId::Mit,
+            &mut vec![],
+        ).unwrap();
+
+        assert_eq!(RAW_MIT, license.contents);
+    }
+
+    const LICENSE_API_URL: &str = "https://api.github.com/repos/owner/repo/license";
+    const RAW_LICENSE_URL: &str = "https://raw.githubusercontent.com/owner/repo/master/LICENSE";
+
+    fn license_api_response(spdx_id: &str) -> String {
+        format!(r#"{{
+            "download_url": "https://raw.githubusercontent.com/owner/repo/master/LICENSE",
+            "content": "Q29weXJpZ2h0IChjKSAyMDE3IFNvbWVvbmUK",
+            "encoding": "base64",
+            "license": {{ "spdx_id": "{}" }}
+        }}"#, spdx_id)
+    }
+
+    /// Runs the lookup for `https://github.com/owner/repo` against `client`
+    fn hound_with(client: MockClient) -> (Option<(LicenseOrigin, RemoteFile)>, Vec<Finding>, Rc<MockClient>) {
+        hound_declared("MIT", client)
+    }
+
+    /// Like `hound_with`, for a crate declaring `declared_license`
+    fn hound_declared(declared_license: &str, client: MockClient) -> (Option<(LicenseOrigin, RemoteFile)>, Vec<Finding>, Rc<MockClient>) {
+        let client = Rc::new(client);
+
+        let forges = Forges::new(vec![Box::new(GitHub::new(
+            "https://github.com",
+            "https://api.github.com",
+            "https://raw.githubusercontent.com",
+            Auth::Anonymous,
+        ))], client.clone());
+
+        let mut findings = vec![];
+        let report = forges.license_file_from_repository(
+            Some("https://github.com/owner/repo"),
+            None,
+            None,
+            "package",
+            declared_license,
+            LicenseId::Mit,
+            &mut findings,
+        );
+
+        (report, findings, client)
+    }
+
+    #[test]
+    fn uses_license_api() {
+        let (report, findings, client) = hound_with(MockClient::new(&[
+            (LICENSE_API_URL, 200, &license_api_response("MIT")),
+        ]));
+
+        assert!(findings.is_empty());
+
+        let (source, license) = report.unwrap();
+        assert_eq!("Copyright (c) 2017 Someone\n", license.contents);
+        match source {
+            LicenseOrigin::GitHubApi { url } => assert_eq!(RAW_LICENSE_URL, url),
+            x => panic!("Unexpected license source {:?}", x),
+        }
+
+        assert_eq!(vec![LICENSE_API_URL.to_string()], client.requested_urls());
+    }
+
+    #[test]
+    fn falls_back_to_repository_when_license_api_is_forbidden() {
+        let (report, _, client) = hound_with(MockClient::new(&[
+            (LICENSE_API_URL, 403, r#"{ "message": "Forbidden" }"#),
+            (RAW_LICENSE_URL, 200, RAW_MIT),
+        ]));
+
+        let (source, license) = report.unwrap();
+        assert_eq!(RAW_MIT, license.contents);
+        match source {
+            LicenseOrigin::GitHubRepo { git_ref, .. } => assert_eq!("master", git_ref),
+            x => panic!("Unexpected license source {:?}", x),
+        }
+
+        assert!(client.requested_urls().contains(&RAW_LICENSE_URL.to_string()));
+    }
+
+    #[test]
+    fn falls_back_to_repository_when_license_api_finds_nothing() {
+        let (report, _, _) = hound_with(MockClient::new(&[
+            (RAW_LICENSE_URL, 200, RAW_MIT),
+        ]));
+
+        assert_eq!(RAW_MIT, report.unwrap().1.contents);
+    }
+
+    #[test]
+    fn records_disagreement_with_license_api() {
+        let (report, findings, _) = hound_with(MockClient::new(&[
+            (LICENSE_API_URL, 200, &license_api_response("Apache-2.0")),
+            (RAW_LICENSE_URL, 200, RAW_MIT),
+        ]));
+
+        match report.unwrap().0 {
+            LicenseOrigin::GitHubRepo { .. } => {},
+            x => panic!("Unexpected license source {:?}", x),
+        }
+
+        assert_eq!(1, findings.len());
+        match findings[0] {
+            Finding::LicenseMismatch { ref service, ref detected_license, chosen_license, ref license_text, .. } => {
+                assert_eq!("github", service);
+                assert_eq!("Apache-2.0", detected_license);
+                assert_eq!(LicenseId::Mit, chosen_license);
+                assert_eq!("Copyright (c) 2017 Someone\n", license_text);
+            },
+        }
+    }
+
+    #[test]
+    fn accepts_other_declared_licenses_from_license_api() {
+        let (report, findings, client) = hound_declared("MIT OR Apache-2.0", MockClient::new(&[
+            (LICENSE_API_URL, 200, &license_api_response("Apache-2.0")),
+            (RAW_LICENSE_URL, 200, RAW_MIT),
+        ]));
+
+        assert!(findings.is_empty());
+        assert_eq!(RAW_MIT, report.unwrap().1.contents);
+        assert!(client.requested_urls().contains(&RAW_LICENSE_URL.to_string()));
+    }
+
+    #[test]
+    fn ignores_unidentified_licenses_from_license_api() {
+        for spdx_id in &["NOASSERTION", "other"] {
+            let (report, findings, _) = hound_with(MockClient::new(&[
+                (LICENSE_API_URL, 200, &license_api_response(spdx_id)),
+                (RAW_LICENSE_URL, 200, RAW_MIT),
+            ]));
+
+            assert!(findings.is_empty(), "{}", spdx_id);
+            assert_eq!(RAW_MIT, report.unwrap().1.contents);
+        }
+    }
+
+    #[test]
+    fn gives_up_when_everything_is_forbidden() {
+        let (report, _, client) = hound_with(MockClient::new(&[
+            (LICENSE_API_URL, 403, ""),
+            ("https://api.github.com/repos/owner/repo", 403, ""),
+        ]));
+
+        assert!(report.is_none());
+        assert!(client.requested_urls().contains(&RAW_LICENSE_URL.to_string()));
+    }
+
+    #[test]
+    fn sends_token() {
+        let client = MockClient::new(&[]);
+        let github = GitHub::new(
+            "https://github.com",
+            "https://api.github.com",
+            "https://raw.githubusercontent.com",
+            Auth::Token("secret".to_string()),
+        );
+
+        github.get_license_file(&Connection { client: &client, limiter: &RateLimiter::default() }, RAW_LICENSE_URL);
+
+        let requests = client.requests();
+        let authorization = requests[0].headers.get_raw("Authorization").unwrap().one().unwrap();
+        assert_eq!(b"Bearer secret", authorization);
+    }
+
+    #[test]
+    #[ignore] // Integration test, talks with github over the Internet (Use `cargo test --ignored`)
+    fn test_with_live_api() {
+        let report = GitHub::default().license_file_from_license_api(
+            &Connection { client: &ReqwestClient, limiter: &RateLimiter::default() },
+            "maghoff",
+            "cargo-license-hound",
+            None,
+            "cargo-license-hound",
+            "MIT",
+            LicenseId::Mit,
+            &mut vec![],
+        );
+
+        println!("{:#?}", report);
+
+        assert!(report.is_some());
+    }
+
+    #[test]
+    #[ignore] // Integration test, talks with github over the Internet (Use `cargo test --ignored`)
+    fn test_with_live_repo() {
+        let forges = Forges::new(vec![Box::new(GitHub::default())], Rc::new(ReqwestClient));
+        let location = forges.forges[0].parse_url("https://github.com/alexcrichton/futures-rs").unwrap();
+
+        let report = forges.license_file_from_tree(0, &location, "master", "", LicenseId::Mit);
+
+        println!("{:#?}", report);
+
+        assert!(report.is_some());
+    }
+}
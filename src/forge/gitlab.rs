@@ -0,0 +1,158 @@
+use regex::Regex;
+
+use config::ForgeConfig;
+use super::*;
+
+#[derive(Deserialize)]
+struct Project {
+    default_branch: Option<String>,
+}
+
+pub struct GitLab {
+    web_url: String,
+    url_pattern: Regex,
+    token: Option<String>,
+}
+
+impl GitLab {
+    pub fn new(web_url: &str) -> GitLab {
+        GitLab {
+            web_url: web_url.trim_end_matches('/').to_string(),
+            // Projects can be nested in any number of groups and subgroups
+            url_pattern: url_pattern(web_url, ".+?", "-/(?:tree|blob)"),
+            token: None,
+        }
+    }
+
+    /// A self-hosted instance from a `[[gitlab]]` section of the
+    /// configuration file
+    pub fn from_config(config: &ForgeConfig) -> GitLab {
+        GitLab {
+            token: config.token.clone(),
+            ..GitLab::new(&config.web_url)
+        }
+    }
+}
+
+impl Default for GitLab {
+    fn default() -> GitLab {
+        GitLab::new("https://gitlab.com")
+    }
+}
+
+impl Forge for GitLab {
+    fn name(&self) -> &'static str {
+        "gitlab"
+    }
+
+    fn serves(&self, url: &str) -> bool {
+        url.starts_with(&format!("{}/", self.web_url))
+    }
+
+    fn request(&self, url: &str) -> Request {
+        let mut request = Request::get(url);
+        if let Some(ref token) = self.token {
+            request.headers.set_raw("PRIVATE-TOKEN", token.clone());
+        }
+        request
+    }
+
+    fn parse_url(&self, url: &str) -> Option<RepoLocation> {
+        self.url_pattern.captures(url).map(RepoLocation::from_captures)
+    }
+
+    fn default_branch(&self, connection: &Connection, location: &RepoLocation) -> Option<String> {
+        let project_id = format!("{}/{}", location.owner, location.repo).replace('/', "%2F");
+        let url = format!("{}/api/v4/projects/{}", self.web_url, project_id);
+
+        let project: Project = try_opt!(fetch_json(self.name(), connection, &self.request(&url)));
+
+        project.default_branch
+    }
+
+    fn raw_file(&self, connection: &Connection, location: &RepoLocation, git_ref: &str, path: &str) -> Option<RemoteFile> {
+        let url = format!("{}/{}/{}/-/raw/{}/{}", self.web_url, location.owner, location.repo, git_ref, path);
+        fetch_text(self.name(), connection, &self.request(&url))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use http::mock::MockClient;
+
+    #[test]
+    fn can_parse_repository_urls() {
+        let gitlab = GitLab::default();
+
+        assert_eq!(
+            Some(RepoLocation {
+                owner: "group/subgroup".to_string(),
+                repo: "project".to_string(),
+                git_ref: None,
+                path: "".to_string(),
+            }),
+            gitlab.parse_url("https://gitlab.com/group/subgroup/project.git")
+        );
+
+        assert_eq!(
+            Some(RepoLocation {
+                owner: "group".to_string(),
+                repo: "project".to_string(),
+                git_ref: Some("main".to_string()),
+                path: "crates/package".to_string(),
+            }),
+            gitlab.parse_url("https://gitlab.com/group/project/-/tree/main/crates/package")
+        );
+    }
+
+    #[test]
+    fn finds_license_file() {
+        let client = MockClient::new(&[
+            ("https://gitlab.example.com/api/v4/projects/group%2Fsubgroup%2Fproject", 200, r#"{ "default_branch": "develop" }"#),
+            ("https://gitlab.example.com/group/subgroup/project/-/raw/develop/COPYING", 200, "Copyright 2017 Someone"),
+        ]);
+
+        let forges = Forges::new(vec![Box::new(GitLab::new("https://gitlab.example.com"))], Rc::new(client));
+
+        let (source, license) = forges.license_file_from_repository(
+            Some("https://gitlab.example.com/group/subgroup/project"),
+            None,
+            None,
+            "package",
+            "MIT",
+            LicenseId::Mpl2,
+            &mut vec![],
+        ).unwrap();
+
+        assert_eq!("Copyright 2017 Someone", license.contents);
+        match source {
+            LicenseOrigin::ForgeRepo { forge, url, git_ref } => {
+                assert_eq!("gitlab", forge);
+                assert_eq!("https://gitlab.example.com/group/subgroup/project/-/raw/develop/COPYING", url);
+                assert_eq!("develop", git_ref);
+            },
+            x => panic!("Unexpected license source {:?}", x),
+        }
+    }
+
+    #[test]
+    fn sends_configured_token() {
+        let client = MockClient::new(&[]);
+        let gitlab = GitLab::from_config(&ForgeConfig {
+            web_url: "https://gitlab.example.com".to_string(),
+            token: Some("secret".to_string()),
+        });
+
+        gitlab.default_branch(&Connection { client: &client, limiter: &RateLimiter::default() }, &RepoLocation {
+            owner: "group".to_string(),
+            repo: "project".to_string(),
+            git_ref: None,
+            path: "".to_string(),
+        });
+
+        let requests = client.requests();
+        assert_eq!("https://gitlab.example.com/api/v4/projects/group%2Fproject", requests[0].url);
+        assert_eq!(b"secret", requests[0].headers.get_raw("PRIVATE-TOKEN").unwrap().one().unwrap());
+    }
+}
@@ -0,0 +1,495 @@
+//! Repository hosting services ("forges") that license files can be
+//! retrieved from when they are missing from the published crate.
+
+pub mod bitbucket;
+mod gh_hosts;
+pub mod gitea;
+pub mod github;
+pub mod gitlab;
+pub mod sourcehut;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use cargo;
+use cargo::core::SourceId;
+use regex::{self, Captures, Regex};
+use reqwest;
+use serde::de::DeserializeOwned;
+use serde_json;
+
+use config::HoundConfig;
+use http::{Client, RateLimiter, Request, Response};
+use license::*;
+use vcs_info::VcsInfo;
+
+/// Branches to try, in order, when looking for a license file in the
+/// current state of a repository. The default branch of the repository,
+/// when it can be determined, is tried first.
+const FALLBACK_BRANCHES: &[&str] = &["master", "main"];
+
+/// A location in a repository, as given in the `repository` field of a
+/// crate manifest. For crates that live in a subdirectory of a larger
+/// repository, this often points into the tree at a given ref.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RepoLocation {
+    pub owner: String,
+    pub repo: String,
+    pub git_ref: Option<String>,
+    pub path: String,
+}
+
+impl RepoLocation {
+    /// Expects the captures of a pattern built by `url_pattern`
+    fn from_captures(captures: Captures) -> RepoLocation {
+        RepoLocation {
+            owner: captures[1].to_string(),
+            repo: captures[2].to_string(),
+            git_ref: captures.get(3).map(|x| x.as_str().to_string()),
+            path: captures.get(4).map(|x| x.as_str().trim_matches('/').to_string()).unwrap_or_default(),
+        }
+    }
+}
+
+/// The host, and port if given, of a URL such as `https://github.com/`
+fn host_of(web_url: &str) -> &str {
+    web_url.splitn(2, "://").last().unwrap().trim_end_matches('/')
+}
+
+/// Builds a pattern recognizing repository URLs on the forge hosted at
+/// `web_url`, capturing owner, repository, ref and path. `owner` is the
+/// pattern for the owner of the repository and `tree` is the pattern for
+/// the part of the URL that precedes the ref when pointing into the tree.
+fn url_pattern(web_url: &str, owner: &str, tree: &str) -> Regex {
+    Regex::new(&format!(
+        r"^https?://(?:www\.)?{}/({})/([^/]+?)(?:\.git)?(?:/{}/([^/]+)(/.*)?)?/?$",
+        regex::escape(host_of(web_url)),
+        owner,
+        tree,
+    )).unwrap()
+}
+
+/// A file retrieved from a forge, along with its `ETag` for revalidating
+/// it later
+#[derive(Debug)]
+pub struct RemoteFile {
+    pub url: String,
+    pub contents: String,
+    pub etag: Option<String>,
+}
+
+impl RemoteFile {
+    /// Gives `None` if the body is not valid UTF-8
+    fn from_response(url: &str, resp: &Response) -> Option<RemoteFile> {
+        Some(RemoteFile {
+            url: url.to_string(),
+            contents: try_opt!(resp.text()),
+            etag: resp.header("ETag").map(|x| x.to_string()),
+        })
+    }
+}
+
+/// The client to send requests to a forge with, and the request budget for
+/// that forge
+pub struct Connection<'a> {
+    client: &'a dyn Client,
+    limiter: &'a RateLimiter,
+}
+
+impl<'a> Connection<'a> {
+    /// Gives `None` if the request failed or was skipped
+    pub fn send(&self, request: &Request) -> Option<Response> {
+        self.limiter.send(self.client, request)
+    }
+}
+
+/// Retrieves the file requested by `request`. Missing files and failed or
+/// skipped requests give `None`.
+fn fetch_text(forge: &str, connection: &Connection, request: &Request) -> Option<RemoteFile> {
+    let resp = try_opt!(connection.send(request));
+
+    if resp.status == reqwest::StatusCode::NotFound {
+        return None;
+    }
+
+    if !resp.status.is_success() {
+        eprintln!("ERROR Unexpected status code from {} ({}): {}", forge, request.url, resp.status);
+        return None;
+    }
+
+    RemoteFile::from_response(&request.url, &resp)
+}
+
+fn fetch_json<T: DeserializeOwned>(forge: &str, connection: &Connection, request: &Request) -> Option<T> {
+    serde_json::from_str(&try_opt!(fetch_text(forge, connection, request)).contents).ok()
+}
+
+/// The outcome of a conditional request for a previously retrieved file
+pub enum Revalidation {
+    NotModified,
+    Modified(RemoteFile),
+
+    /// The file is no longer there
+    Gone,
+
+    /// The forge could not be reached, so the file may or may not have
+    /// changed
+    Unavailable,
+}
+
+pub trait Forge {
+    /// Identifies the forge in the report, such as `"gitlab"`
+    fn name(&self) -> &'static str;
+
+    /// Recognizes URLs of files retrieved by `raw_file`
+    fn serves(&self, url: &str) -> bool;
+
+    /// Builds a request to the forge, including any credentials
+    fn request(&self, url: &str) -> Request {
+        Request::get(url)
+    }
+
+    /// Recognizes repository URLs hosted on this forge
+    fn parse_url(&self, url: &str) -> Option<RepoLocation>;
+
+    /// The branch a clone of the repository checks out by default
+    fn default_branch(&self, connection: &Connection, location: &RepoLocation) -> Option<String>;
+
+    /// Retrieves the file at `path` in the repository, as of `git_ref`
+    fn raw_file(&self, connection: &Connection, location: &RepoLocation, git_ref: &str, path: &str) -> Option<RemoteFile>;
+
+    /// Forge-specific license detection, such as the GitHub license API.
+    /// This is tried before looking for license files in the current state
+    /// of the repository. Licenses found that are not among those of the
+    /// `declared_license` expression are added to `findings`.
+    fn license_file_from_api(&self, _connection: &Connection, _location: &RepoLocation, _git_ref: Option<&str>, _package_name: &str, _declared_license: &str, _chosen_license: LicenseId, _findings: &mut Vec<Finding>) -> Option<(LicenseOrigin, RemoteFile)> {
+        None
+    }
+
+    fn license_origin(&self, url: String, git_ref: &str) -> LicenseOrigin {
+        LicenseOrigin::ForgeRepo {
+            forge: self.name().to_string(),
+            url,
+            git_ref: git_ref.to_string(),
+        }
+    }
+}
+
+/// A `path` in a repository followed by each of its ancestors, ending with
+/// the root of the repository.
+fn path_and_ancestors(path: &str) -> Vec<&str> {
+    let mut dirs = Vec::new();
+    let mut path = path.trim_matches('/');
+
+    while !path.is_empty() {
+        dirs.push(path);
+        path = match path.rfind('/') {
+            Some(i) => &path[..i],
+            None => "",
+        };
+    }
+    dirs.push("");
+
+    dirs
+}
+
+pub struct Forges {
+    forges: Vec<Box<dyn Forge>>,
+    client: Rc<dyn Client>,
+
+    /// The request budget of each forge, by index in `forges`
+    limiters: Vec<RateLimiter>,
+    default_branches: RefCell<HashMap<(usize, String, String), Option<String>>>,
+}
+
+impl Forges {
+    pub fn new(forges: Vec<Box<dyn Forge>>, client: Rc<dyn Client>) -> Forges {
+        Forges {
+            limiters: forges.iter().map(|_| RateLimiter::default()).collect(),
+            forges,
+            client,
+            default_branches: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The public instances of all supported forges, along with any GitHub
+    /// Enterprise, GitLab and Gitea instances given in the configuration
+    pub fn from_config(config: &HoundConfig, client: Rc<dyn Client>) -> Forges {
+        let mut forges: Vec<Box<dyn Forge>> = vec![
+            Box::new(github::GitHub::public(&config.github)),
+        ];
+
+        for enterprise in &config.github_enterprise {
+            match github::GitHub::enterprise(enterprise) {
+                Some(github) => forges.push(Box::new(github)),
+                None => eprintln!("WARN Ignoring [[github-enterprise]] without web-url"),
+            }
+        }
+
+        forges.push(Box::new(gitlab::GitLab::default()));
+        for gitlab in &config.gitlab {
+            forges.push(Box::new(gitlab::GitLab::from_config(gitlab)));
+        }
+
+        forges.push(Box::new(gitea::Gitea::default()));
+        for gitea in &config.gitea {
+            forges.push(Box::new(gitea::Gitea::from_config(gitea)));
+        }
+        forges.push(Box::new(bitbucket::Bitbucket::default()));
+        forges.push(Box::new(sourcehut::SourceHut::default()));
+
+        Forges::new(forges, client)
+    }
+
+    /// The number of requests skipped across all forges because their rate
+    /// limit was exhausted
+    pub fn skipped_lookups(&self) -> usize {
+        self.limiters.iter().map(|limiter| limiter.skipped()).sum()
+    }
+
+    fn connection(&self, forge: usize) -> Connection<'_> {
+        Connection {
+            client: &*self.client,
+            limiter: &self.limiters[forge],
+        }
+    }
+
+    fn locate(&self, url: &str) -> Option<(usize, RepoLocation)> {
+        self.forges.iter().enumerate()
+            .filter_map(|(i, forge)| forge.parse_url(url).map(|location| (i, location)))
+            .next()
+    }
+
+    fn default_branch(&self, forge: usize, location: &RepoLocation) -> Option<String> {
+        let key = (forge, location.owner.clone(), location.repo.clone());
+
+        if let Some(default_branch) = self.default_branches.borrow().get(&key) {
+            return default_branch.clone();
+        }
+
+        let default_branch = self.forges[forge].default_branch(&self.connection(forge), location);
+        self.default_branches.borrow_mut().insert(key, default_branch.clone());
+
+        default_branch
+    }
+
+    /// Refs to try, in order, when looking for a license file in the
+    /// current state of a repository
+    fn candidate_refs(&self, forge: usize, location: &RepoLocation) -> Vec<String> {
+        let mut git_refs: Vec<String> = location.git_ref.iter().cloned().collect();
+
+        let branches = self.default_branch(forge, location).into_iter()
+            .chain(FALLBACK_BRANCHES.iter().map(|x| x.to_string()));
+
+        for branch in branches {
+            if !git_refs.contains(&branch) {
+                git_refs.push(branch);
+            }
+        }
+
+        git_refs
+    }
+
+    fn license_file_from_tree(&self, forge: usize, location: &RepoLocation, git_ref: &str, path: &str, chosen_license: LicenseId) -> Option<(LicenseOrigin, RemoteFile)> {
+        let connection = self.connection(forge);
+        let forge = &self.forges[forge];
+
+        for dir in path_and_ancestors(path) {
+            for (a, b, c) in chosen_license.guess_filenames() {
+                let file_path =
+                    if dir.is_empty() {
+                        format!("{}{}{}", a, b, c)
+                    } else {
+                        format!("{}/{}{}{}", dir, a, b, c)
+                    };
+
+                if let Some(file) = forge.raw_file(&connection, location, git_ref, &file_path) {
+                    return Some((forge.license_origin(file.url.clone(), git_ref), file));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn license_file_from_repository(&self, repo_url: Option<&str>, git_ref: Option<&str>, vcs_info: Option<&VcsInfo>, package_name: &str, declared_license: &str, chosen_license: LicenseId, findings: &mut Vec<Finding>) -> Option<(LicenseOrigin, RemoteFile)> {
+        let (forge, location) = try_opt!(self.locate(try_opt!(repo_url)));
+
+        let from_tree = || {
+            let git_refs = match git_ref {
+                Some(git_ref) => vec![git_ref.to_string()],
+                None => self.candidate_refs(forge, &location),
+            };
+
+            git_refs.iter()
+                .filter_map(|git_ref| self.license_file_from_tree(forge, &location, git_ref, &location.path, chosen_license))
+                .next()
+        };
+
+        // For crates living in a subdirectory of a larger repository, a
+        // license API would only find the license of the repository as a whole
+        let in_subdirectory = !location.path.is_empty();
+
+        // The commit the crate was published from is the most accurate
+        // reference, so try that before falling back to the current state of
+        // the repository
+        vcs_info.and_then(|vcs_info| self.license_file_from_tree(forge, &location, &vcs_info.git.sha1, &vcs_info.path_in_vcs, chosen_license))
+            .or_else(|| if in_subdirectory { from_tree() } else { None })
+            .or_else(|| self.forges[forge].license_file_from_api(&self.connection(forge), &location, git_ref, package_name, declared_license, chosen_license, findings))
+            .or_else(|| if in_subdirectory { None } else { from_tree() })
+    }
+
+    /// Checks whether the file previously retrieved from `url` has changed
+    /// since it had the given `etag`
+    pub fn revalidate(&self, url: &str, etag: &str) -> Revalidation {
+        let forge = match self.forges.iter().position(|forge| forge.serves(url)) {
+            Some(forge) => forge,
+            None => return Revalidation::Unavailable,
+        };
+
+        let mut request = self.forges[forge].request(url);
+        request.headers.set_raw("If-None-Match", etag.to_string());
+
+        let resp = match self.connection(forge).send(&request) {
+            Some(resp) => resp,
+            None => return Revalidation::Unavailable,
+        };
+
+        if resp.status == reqwest::StatusCode::NotModified {
+            return Revalidation::NotModified;
+        }
+
+        if resp.status == reqwest::StatusCode::NotFound {
+            return Revalidation::Gone;
+        }
+
+        if !resp.status.is_success() {
+            return Revalidation::Unavailable;
+        }
+
+        match RemoteFile::from_response(url, &resp) {
+            Some(file) => Revalidation::Modified(file),
+            None => Revalidation::Unavailable,
+        }
+    }
+
+    pub fn license_file(&self, package: &cargo::core::Package, source_id: &SourceId, chosen_license: LicenseId, findings: &mut Vec<Finding>) -> Option<(LicenseOrigin, RemoteFile)> {
+        let declared_license = package.manifest().metadata().license.as_ref().map_or("", String::as_str);
+
+        // For git dependencies, the locked revision of the repository we
+        // actually depend on is more accurate than the declared repository
+        if source_id.is_git() {
+            let repo_url = source_id.url().to_string();
+            self.license_file_from_repository(
+                Some(&repo_url),
+                source_id.precise(),
+                None,
+                package.name(),
+                declared_license,
+                chosen_license,
+                findings,
+            )
+        } else {
+            self.license_file_from_repository(
+                package.manifest().metadata().repository.as_ref().map(|x| &**x),
+                None,
+                VcsInfo::from_package_root(package.root()).as_ref(),
+                package.name(),
+                declared_license,
+                chosen_license,
+                findings,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use http;
+
+    #[test]
+    fn walks_up_to_repository_root() {
+        assert_eq!(vec!["a/b/c", "a/b", "a", ""], path_and_ancestors("/a/b/c/"));
+        assert_eq!(vec![""], path_and_ancestors(""));
+    }
+
+    #[test]
+    fn can_select_forge_by_url() {
+        let forges = Forges::from_config(&HoundConfig::default(), Rc::new(http::ReqwestClient));
+
+        let names = [
+            "https://github.com/maghoff/cargo-license-hound",
+            "https://gitlab.com/gitlab-org/gitlab",
+            "https://codeberg.org/forgejo/forgejo",
+            "https://bitbucket.org/atlassian/python-bitbucket",
+            "https://git.sr.ht/~sircmpwn/git.sr.ht",
+        ].iter()
+            .map(|url| forges.locate(url).map(|(i, _)| forges.forges[i].name()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![Some("github"), Some("gitlab"), Some("gitea"), Some("bitbucket"), Some("sourcehut")],
+            names
+        );
+
+        assert!(forges.locate("https://example.com/maghoff/cargo-license-hound").is_none());
+    }
+
+    #[test]
+    fn looks_up_default_branch_once_per_repository() {
+        const REPOSITORY_URL: &str = "https://gitea.example.com/api/v1/repos/owner/repo";
+
+        let client = Rc::new(http::mock::MockClient::new(&[
+            (REPOSITORY_URL, 200, r#"{ "default_branch": "trunk" }"#),
+            ("https://gitea.example.com/owner/repo/raw/trunk/LICENSE", 200, "Copyright (c) 2017 Someone"),
+        ]));
+
+        let forges = Forges::new(vec![Box::new(gitea::Gitea::new("https://gitea.example.com"))], client.clone());
+
+        // Crates from the same repository, as for a workspace
+        for repo_url in &["https://gitea.example.com/owner/repo", "https://gitea.example.com/owner/repo.git"] {
+            let (source, _) = forges.license_file_from_repository(
+                Some(repo_url),
+                None,
+                None,
+                "package",
+                "MIT",
+                LicenseId::Mit,
+                &mut vec![],
+            ).unwrap();
+
+            match source {
+                LicenseOrigin::ForgeRepo { git_ref, .. } => assert_eq!("trunk", git_ref),
+                x => panic!("Unexpected license source {:?}", x),
+            }
+        }
+
+        assert_eq!(1, client.requested_urls().iter().filter(|x| *x == REPOSITORY_URL).count());
+    }
+
+    #[test]
+    fn can_revalidate_cached_files() {
+        let client = http::mock::MockClient::new(&[
+            ("https://gitea.example.com/owner/repo/raw/main/LICENSE", 304, ""),
+        ]);
+
+        let forges = Forges::new(vec![Box::new(gitea::Gitea::new("https://gitea.example.com"))], Rc::new(client));
+
+        match forges.revalidate("https://gitea.example.com/owner/repo/raw/main/LICENSE", "\"abc\"") {
+            Revalidation::NotModified => {},
+            _ => panic!("Expected the file to be unmodified"),
+        }
+
+        match forges.revalidate("https://gitea.example.com/owner/repo/raw/main/COPYING", "\"abc\"") {
+            Revalidation::Gone => {},
+            _ => panic!("Expected the file to be gone"),
+        }
+
+        match forges.revalidate("https://example.com/owner/repo/raw/main/LICENSE", "\"abc\"") {
+            Revalidation::Unavailable => {},
+            _ => panic!("Expected no forge to serve the file"),
+        }
+    }
+}
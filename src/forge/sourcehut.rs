@@ -0,0 +1,116 @@
+use regex::Regex;
+
+use super::*;
+
+pub struct SourceHut {
+    web_url: String,
+    url_pattern: Regex,
+}
+
+impl SourceHut {
+    pub fn new(web_url: &str) -> SourceHut {
+        SourceHut {
+            web_url: web_url.trim_end_matches('/').to_string(),
+            url_pattern: url_pattern(web_url, "~[^/]+", "tree"),
+        }
+    }
+}
+
+impl Default for SourceHut {
+    fn default() -> SourceHut {
+        SourceHut::new("https://git.sr.ht")
+    }
+}
+
+impl Forge for SourceHut {
+    fn name(&self) -> &'static str {
+        "sourcehut"
+    }
+
+    fn serves(&self, url: &str) -> bool {
+        url.starts_with(&format!("{}/", self.web_url))
+    }
+
+    fn parse_url(&self, url: &str) -> Option<RepoLocation> {
+        let mut location = RepoLocation::from_captures(try_opt!(self.url_pattern.captures(url)));
+
+        // Paths in the tree are given as /tree/<ref>/item/<path>
+        if location.path == "item" || location.path.starts_with("item/") {
+            location.path = location.path["item".len()..].trim_start_matches('/').to_string();
+        }
+
+        Some(location)
+    }
+
+    fn default_branch(&self, _connection: &Connection, _location: &RepoLocation) -> Option<String> {
+        // The sourcehut API requires authentication, so fall back to
+        // trying common branch names
+        None
+    }
+
+    fn raw_file(&self, connection: &Connection, location: &RepoLocation, git_ref: &str, path: &str) -> Option<RemoteFile> {
+        let url = format!("{}/{}/{}/blob/{}/{}", self.web_url, location.owner, location.repo, git_ref, path);
+        fetch_text(self.name(), connection, &self.request(&url))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use http::mock::MockClient;
+
+    #[test]
+    fn can_parse_repository_urls() {
+        let sourcehut = SourceHut::default();
+
+        assert_eq!(
+            Some(RepoLocation {
+                owner: "~owner".to_string(),
+                repo: "repo".to_string(),
+                git_ref: None,
+                path: "".to_string(),
+            }),
+            sourcehut.parse_url("https://git.sr.ht/~owner/repo")
+        );
+
+        assert_eq!(
+            Some(RepoLocation {
+                owner: "~owner".to_string(),
+                repo: "repo".to_string(),
+                git_ref: Some("master".to_string()),
+                path: "package".to_string(),
+            }),
+            sourcehut.parse_url("https://git.sr.ht/~owner/repo/tree/master/item/package")
+        );
+
+        assert_eq!(None, sourcehut.parse_url("https://git.sr.ht/owner/repo"));
+    }
+
+    #[test]
+    fn finds_license_file() {
+        let client = MockClient::new(&[
+            ("https://sourcehut.example.com/~owner/repo/blob/main/LICENSE", 200, "Copyright (c) 2017 Someone"),
+        ]);
+
+        let forges = Forges::new(vec![Box::new(SourceHut::new("https://sourcehut.example.com"))], Rc::new(client));
+
+        let (source, license) = forges.license_file_from_repository(
+            Some("https://sourcehut.example.com/~owner/repo"),
+            None,
+            None,
+            "package",
+            "MIT",
+            LicenseId::Mit,
+            &mut vec![],
+        ).unwrap();
+
+        assert_eq!("Copyright (c) 2017 Someone", license.contents);
+        match source {
+            LicenseOrigin::ForgeRepo { forge, git_ref, .. } => {
+                assert_eq!("sourcehut", forge);
+                assert_eq!("main", git_ref);
+            },
+            x => panic!("Unexpected license source {:?}", x),
+        }
+    }
+}
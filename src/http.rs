@@ -0,0 +1,391 @@
+use std::cell::Cell;
+use std::io;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest;
+use reqwest::header::{Header, Headers};
+
+lazy_static! {
+    static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::new();
+}
+
+pub const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Attempts for requests that fail with transient server errors
+const MAX_ATTEMPTS: u32 = 3;
+
+#[derive(Debug)]
+pub enum Error {
+    Reqwest(reqwest::Error),
+    Io(io::Error),
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(other: reqwest::Error) -> Error {
+        Error::Reqwest(other)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(other: io::Error) -> Error {
+        Error::Io(other)
+    }
+}
+
+/// A GET request. Unlike `reqwest::RequestBuilder`, it can be sent any
+/// number of times, which is necessary for retrying.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub url: String,
+    pub headers: Headers,
+}
+
+impl Request {
+    pub fn get(url: &str) -> Request {
+        let mut request = Request { url: url.to_string(), headers: Headers::new() };
+
+        request.header(reqwest::header::UserAgent::new(USER_AGENT));
+
+        request
+    }
+
+    pub fn header<H: Header>(&mut self, header: H) -> &mut Request {
+        self.headers.set(header);
+        self
+    }
+}
+
+/// A response with its body read in full
+#[derive(Debug)]
+pub struct Response {
+    pub status: reqwest::StatusCode,
+    pub headers: Headers,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let raw = try_opt!(try_opt!(self.headers.get_raw(name)).one());
+        ::std::str::from_utf8(raw).ok()
+    }
+
+    pub fn text(&self) -> Option<String> {
+        String::from_utf8(self.body.clone()).ok()
+    }
+}
+
+/// Performs the HTTP requests for license-hound. Everything that is fetched
+/// from the network goes through this, so tests can provide canned
+/// responses instead.
+pub trait Client {
+    fn send(&self, request: &Request) -> Result<Response, Error>;
+}
+
+/// Sends requests over the network
+pub struct ReqwestClient;
+
+impl Client for ReqwestClient {
+    fn send(&self, request: &Request) -> Result<Response, Error> {
+        use std::io::prelude::*;
+
+        let mut builder = HTTP_CLIENT.get(&request.url);
+        builder.headers(request.headers.clone());
+
+        let mut resp = builder.send()?;
+
+        let mut body = Vec::new();
+        resp.read_to_end(&mut body)?;
+
+        Ok(Response {
+            status: resp.status(),
+            headers: resp.headers().clone(),
+            body,
+        })
+    }
+}
+
+fn header_u64(resp: &Response, name: &str) -> Option<u64> {
+    try_opt!(resp.header(name)).trim().parse().ok()
+}
+
+/// Keeps track of the request budget of a host, as announced by the
+/// `X-RateLimit-*` and `Retry-After` headers of its responses.
+///
+/// When the budget is exhausted, requests are paused until it is replenished
+/// if that is within `max_wait`. Otherwise they are skipped, and counted,
+/// until then.
+pub struct RateLimiter {
+    max_wait: Duration,
+    backoff: Duration,
+    exhausted_until: Cell<Option<SystemTime>>,
+    skipped: Cell<usize>,
+    warned: Cell<bool>,
+}
+
+impl RateLimiter {
+    /// `backoff` is the delay before retrying a request that failed with a
+    /// server error, doubling for each subsequent attempt.
+    pub fn new(max_wait: Duration, backoff: Duration) -> RateLimiter {
+        RateLimiter {
+            max_wait,
+            backoff,
+            exhausted_until: Cell::new(None),
+            skipped: Cell::new(0),
+            warned: Cell::new(false),
+        }
+    }
+
+    /// The number of requests skipped because the budget was exhausted
+    pub fn skipped(&self) -> usize {
+        self.skipped.get()
+    }
+
+    /// Updates the budget from a response. Gives `true` if the request was
+    /// refused due to rate limiting.
+    fn observe(&self, resp: &Response) -> bool {
+        let now = SystemTime::now();
+
+        let remaining = header_u64(resp, "X-RateLimit-Remaining");
+        let reset = header_u64(resp, "X-RateLimit-Reset")
+            .map(|x| UNIX_EPOCH + Duration::from_secs(x));
+        let retry_after = header_u64(resp, "Retry-After")
+            .map(|x| now + Duration::from_secs(x));
+
+        let refused =
+            (resp.status == reqwest::StatusCode::Forbidden || resp.status == reqwest::StatusCode::TooManyRequests) &&
+            (remaining == Some(0) || retry_after.is_some());
+
+        if refused {
+            let until = retry_after.or(reset).unwrap_or(now + self.max_wait);
+            self.exhausted_until.set(Some(until));
+        } else if remaining == Some(0) {
+            self.exhausted_until.set(reset);
+        }
+
+        refused
+    }
+
+    /// Pauses until the budget is replenished, if necessary. Gives `false`
+    /// if that would take longer than `max_wait`.
+    fn wait_for_budget(&self) -> bool {
+        let until = match self.exhausted_until.get() {
+            Some(until) => until,
+            None => return true,
+        };
+
+        let wait = match until.duration_since(SystemTime::now()) {
+            Ok(wait) => wait,
+            Err(_) => {
+                self.exhausted_until.set(None);
+                return true;
+            },
+        };
+
+        if wait > self.max_wait {
+            if !self.warned.replace(true) {
+                eprintln!(
+                    "WARN Rate limit exhausted for the next {} seconds. Skipping remote lookups until then",
+                    wait.as_secs()
+                );
+            }
+            return false;
+        }
+
+        eprintln!("NOTE Rate limit exhausted. Pausing for {} seconds", wait.as_secs());
+        thread::sleep(wait);
+        self.exhausted_until.set(None);
+
+        true
+    }
+
+    /// Sends `request` with `client`. Requests that fail with a server
+    /// error or are refused due to rate limiting are retried. Gives `None`
+    /// if the request could not be sent at all.
+    pub fn send(&self, client: &dyn Client, request: &Request) -> Option<Response> {
+        let mut delay = self.backoff;
+        let mut attempt = 1;
+
+        loop {
+            if !self.wait_for_budget() {
+                self.skipped.set(self.skipped.get() + 1);
+                return None;
+            }
+
+            let resp = try_opt!(client.send(request).ok());
+            let refused = self.observe(&resp);
+
+            if attempt == MAX_ATTEMPTS {
+                if refused {
+                    self.skipped.set(self.skipped.get() + 1);
+                    return None;
+                }
+                return Some(resp);
+            }
+
+            if !refused {
+                if !resp.status.is_server_error() {
+                    return Some(resp);
+                }
+
+                eprintln!(
+                    "WARN Unexpected status code from {}: {}. Retrying in {} seconds",
+                    request.url, resp.status, delay.as_secs()
+                );
+                thread::sleep(delay);
+                delay *= 2;
+            }
+
+            attempt += 1;
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> RateLimiter {
+        RateLimiter::new(Duration::from_secs(60), Duration::from_secs(1))
+    }
+}
+
+/// Canned responses for tests, so they need neither the network nor a
+/// server
+#[cfg(test)]
+pub mod mock {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    use super::*;
+
+    pub struct MockClient {
+        responses: HashMap<String, (u16, Vec<(String, String)>, Vec<u8>)>,
+        requests: RefCell<Vec<Request>>,
+    }
+
+    impl MockClient {
+        /// Serves `responses` of `(url, status, body)`. Requests for other
+        /// URLs get `404 Not Found`.
+        pub fn new(responses: &[(&str, u16, &str)]) -> MockClient {
+            MockClient::with_bytes(&responses.iter()
+                .map(|&(url, status, body)| (url, status, body.as_bytes().to_vec()))
+                .collect::<Vec<_>>())
+        }
+
+        /// Like `new`, for bodies that are not text
+        pub fn with_bytes(responses: &[(&str, u16, Vec<u8>)]) -> MockClient {
+            MockClient {
+                responses: responses.iter()
+                    .map(|&(url, status, ref body)| (url.to_string(), (status, vec![], body.clone())))
+                    .collect(),
+                requests: RefCell::new(vec![]),
+            }
+        }
+
+        /// Adds a response header to the response for `url`
+        pub fn with_header(mut self, url: &str, name: &str, value: &str) -> MockClient {
+            self.responses.get_mut(url).unwrap().1.push((name.to_string(), value.to_string()));
+            self
+        }
+
+        /// The requests sent so far, in order
+        pub fn requests(&self) -> Vec<Request> {
+            self.requests.borrow().clone()
+        }
+
+        pub fn requested_urls(&self) -> Vec<String> {
+            self.requests.borrow().iter().map(|x| x.url.clone()).collect()
+        }
+    }
+
+    impl Client for MockClient {
+        fn send(&self, request: &Request) -> Result<Response, Error> {
+            self.requests.borrow_mut().push(request.clone());
+
+            let (status, headers, body) = self.responses.get(&request.url).cloned()
+                .unwrap_or_else(|| (404, vec![], vec![]));
+
+            let mut response_headers = Headers::new();
+            for (name, value) in headers {
+                response_headers.set_raw(name, value);
+            }
+
+            Ok(Response {
+                status: reqwest::StatusCode::try_from(status).unwrap(),
+                headers: response_headers,
+                body,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::mock::MockClient;
+
+    const URL: &str = "https://example.com/LICENSE";
+
+    fn reset_in_an_hour() -> String {
+        let reset = SystemTime::now() + Duration::from_secs(3600);
+        reset.duration_since(UNIX_EPOCH).unwrap().as_secs().to_string()
+    }
+
+    #[test]
+    fn exhausted_budget_skips_requests() {
+        let client = MockClient::new(&[(URL, 403, "")])
+            .with_header(URL, "X-RateLimit-Remaining", "0")
+            .with_header(URL, "X-RateLimit-Reset", &reset_in_an_hour());
+        let limiter = RateLimiter::new(Duration::from_secs(0), Duration::from_secs(0));
+
+        assert!(limiter.send(&client, &Request::get(URL)).is_none());
+        assert!(limiter.send(&client, &Request::get(URL)).is_none());
+
+        assert_eq!(1, client.requests().len());
+        assert_eq!(2, limiter.skipped());
+    }
+
+    #[test]
+    fn last_request_in_budget_is_not_refused() {
+        let client = MockClient::new(&[(URL, 200, "MIT")])
+            .with_header(URL, "X-RateLimit-Remaining", "0")
+            .with_header(URL, "X-RateLimit-Reset", &reset_in_an_hour());
+        let limiter = RateLimiter::new(Duration::from_secs(0), Duration::from_secs(0));
+
+        let resp = limiter.send(&client, &Request::get(URL)).unwrap();
+        assert_eq!(Some("MIT".to_string()), resp.text());
+
+        assert!(limiter.send(&client, &Request::get(URL)).is_none());
+        assert_eq!(1, limiter.skipped());
+    }
+
+    #[test]
+    fn forbidden_without_rate_limit_is_not_retried() {
+        let client = MockClient::new(&[(URL, 403, "")])
+            .with_header(URL, "X-RateLimit-Remaining", "4999");
+        let limiter = RateLimiter::new(Duration::from_secs(0), Duration::from_secs(0));
+
+        let resp = limiter.send(&client, &Request::get(URL)).unwrap();
+        assert_eq!(reqwest::StatusCode::Forbidden, resp.status);
+        assert_eq!(1, client.requests().len());
+    }
+
+    #[test]
+    fn retry_after_pauses_requests() {
+        let client = MockClient::new(&[(URL, 429, "")])
+            .with_header(URL, "Retry-After", "0");
+        let limiter = RateLimiter::new(Duration::from_secs(60), Duration::from_secs(0));
+
+        assert!(limiter.send(&client, &Request::get(URL)).is_none());
+        assert_eq!(MAX_ATTEMPTS as usize, client.requests().len());
+    }
+
+    #[test]
+    fn server_errors_are_retried() {
+        let client = MockClient::new(&[(URL, 503, "")]);
+        let limiter = RateLimiter::new(Duration::from_secs(60), Duration::from_secs(0));
+
+        let resp = limiter.send(&client, &Request::get(URL)).unwrap();
+        assert_eq!(reqwest::StatusCode::ServiceUnavailable, resp.status);
+        assert_eq!(MAX_ATTEMPTS as usize, client.requests().len());
+        assert_eq!(0, limiter.skipped());
+    }
+}
@@ -1,7 +1,10 @@
 use std::{iter, slice};
 
+use cargo::util::Sha256;
 use itertools;
 
+use timestamp;
+
 const LICENSE_BASE_NAMES: &[&str] = &[
     "LICENSE",
     "COPYING",
@@ -14,20 +17,40 @@ const LICENSE_EXTENSIONS: &[&str] = &[
     ".md"
 ];
 
-#[derive(Debug, Copy, Clone, Serialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LicenseId {
+    Apache2,
+    Bsd2Clause,
     Bsd3Clause,
+    Isc,
     Mit,
     Mpl2,
+    Zlib,
 }
 
+/// The licenses license-hound can settle on, in order of preference when a
+/// crate may be used under several of them. Permissive licenses come first.
+pub const SUPPORTED_LICENSES: &[LicenseId] = &[
+    LicenseId::Mit,
+    LicenseId::Apache2,
+    LicenseId::Bsd3Clause,
+    LicenseId::Bsd2Clause,
+    LicenseId::Isc,
+    LicenseId::Zlib,
+    LicenseId::Mpl2,
+];
+
 impl LicenseId {
     pub fn suffixes(&self) -> &'static [&'static str] {
         use LicenseId::*;
         match self {
             &Mit => &[ "-MIT" ],
+            &Apache2 => &[ "-APACHE" ],
+            &Bsd2Clause => &[ ],
             &Bsd3Clause => &[ ],
+            &Isc => &[ ],
             &Mpl2 => &[ ],
+            &Zlib => &[ ],
         }
     }
 
@@ -54,15 +77,291 @@ impl LicenseId {
         use LicenseId::*;
         match self {
             &Mit => "MIT",
+            &Apache2 => "Apache-2.0",
+            &Bsd2Clause => "BSD-2-Clause",
             &Bsd3Clause => "BSD-3-Clause",
+            &Isc => "ISC",
             &Mpl2 => "MPL-2.0",
+            &Zlib => "Zlib",
+        }
+    }
+
+    pub fn category(&self) -> Category {
+        Category::of(self.spdx_id(), None)
+    }
+
+    pub fn from_spdx_id(spdx_id: &str) -> Option<LicenseId> {
+        SUPPORTED_LICENSES.iter().cloned().find(|x| x.spdx_id() == spdx_id)
+    }
+}
+
+/// What a license asks of those who use the licensed work, broadly
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Category {
+    /// Asks for little more than attribution, like MIT or Apache-2.0
+    Permissive,
+
+    /// Changes to the licensed files must be shared, but not the larger
+    /// work, like MPL-2.0 or LGPL-2.1
+    WeakCopyleft,
+
+    /// Works derived from the licensed work must be shared under the same
+    /// license when distributed, like GPL-3.0
+    StrongCopyleft,
+
+    /// Like strong copyleft, but also when the work is used over a network,
+    /// like AGPL-3.0
+    NetworkCopyleft,
+
+    /// Forbids commercial use, like CC-BY-NC-4.0
+    NonCommercial,
+
+    /// Proprietary licenses and licenses that are not recognized
+    Proprietary,
+}
+
+use self::Category::*;
+
+/// Categories of SPDX license identifiers, without `-only`, `-or-later` or
+/// `+`. Licenses not listed here are considered proprietary.
+const CATEGORIES: &[(&str, Category)] = &[
+    ("0BSD", Permissive),
+    ("Apache-1.1", Permissive),
+    ("Apache-2.0", Permissive),
+    ("Artistic-2.0", Permissive),
+    ("BSD-1-Clause", Permissive),
+    ("BSD-2-Clause", Permissive),
+    ("BSD-3-Clause", Permissive),
+    ("BSD-3-Clause-Clear", Permissive),
+    ("BSL-1.0", Permissive),
+    ("CC-BY-3.0", Permissive),
+    ("CC-BY-4.0", Permissive),
+    ("CC0-1.0", Permissive),
+    ("curl", Permissive),
+    ("ISC", Permissive),
+    ("MIT", Permissive),
+    ("MIT-0", Permissive),
+    ("NCSA", Permissive),
+    ("OpenSSL", Permissive),
+    ("PostgreSQL", Permissive),
+    ("PSF-2.0", Permissive),
+    ("Python-2.0", Permissive),
+    ("Unicode-3.0", Permissive),
+    ("Unicode-DFS-2016", Permissive),
+    ("Unlicense", Permissive),
+    ("W3C", Permissive),
+    ("WTFPL", Permissive),
+    ("X11", Permissive),
+    ("Zlib", Permissive),
+
+    ("CDDL-1.0", WeakCopyleft),
+    ("CDDL-1.1", WeakCopyleft),
+    ("CPL-1.0", WeakCopyleft),
+    ("EPL-1.0", WeakCopyleft),
+    ("EPL-2.0", WeakCopyleft),
+    ("LGPL-2.0", WeakCopyleft),
+    ("LGPL-2.1", WeakCopyleft),
+    ("LGPL-3.0", WeakCopyleft),
+    ("MPL-1.1", WeakCopyleft),
+    ("MPL-2.0", WeakCopyleft),
+    ("MPL-2.0-no-copyleft-exception", WeakCopyleft),
+    ("MS-RL", WeakCopyleft),
+
+    ("CC-BY-SA-3.0", StrongCopyleft),
+    ("CC-BY-SA-4.0", StrongCopyleft),
+    ("EUPL-1.1", StrongCopyleft),
+    ("EUPL-1.2", StrongCopyleft),
+    ("GPL-2.0", StrongCopyleft),
+    ("GPL-3.0", StrongCopyleft),
+
+    ("AGPL-1.0", NetworkCopyleft),
+    ("AGPL-3.0", NetworkCopyleft),
+    ("OSL-3.0", NetworkCopyleft),
+    ("RPL-1.5", NetworkCopyleft),
+    ("SSPL-1.0", NetworkCopyleft),
+
+    ("CC-BY-NC-4.0", NonCommercial),
+    ("CC-BY-NC-ND-4.0", NonCommercial),
+    ("CC-BY-NC-SA-4.0", NonCommercial),
+    ("PolyForm-Noncommercial-1.0.0", NonCommercial),
+];
+
+/// Exceptions that allow linking with the licensed work without the
+/// copyleft extending to the larger work
+const LINKING_EXCEPTIONS: &[&str] = &[
+    "Classpath-exception-2.0",
+    "GCC-exception-2.0",
+    "GCC-exception-3.1",
+    "LLVM-exception",
+];
+
+impl Category {
+    pub fn id(&self) -> &'static str {
+        match *self {
+            Permissive => "permissive",
+            WeakCopyleft => "weak-copyleft",
+            StrongCopyleft => "strong-copyleft",
+            NetworkCopyleft => "network-copyleft",
+            NonCommercial => "non-commercial",
+            Proprietary => "proprietary",
         }
     }
+
+    /// The category of an SPDX license identifier, optionally with an
+    /// exception as in `GPL-2.0 WITH Classpath-exception-2.0`. Identifiers
+    /// are compared case-insensitively, as required by SPDX.
+    pub fn of(spdx_id: &str, exception: Option<&str>) -> Category {
+        let base = base_id(spdx_id);
+
+        let category = CATEGORIES.iter()
+            .find(|&&(id, _)| id.eq_ignore_ascii_case(base))
+            .map(|&(_, category)| category)
+            .unwrap_or(Proprietary);
+
+        let linking_exception = exception
+            .map(|x| LINKING_EXCEPTIONS.iter().any(|y| y.eq_ignore_ascii_case(x)))
+            .unwrap_or(false);
+
+        match category {
+            StrongCopyleft if linking_exception => WeakCopyleft,
+            category => category,
+        }
+    }
+}
+
+/// The license an SPDX identifier refers to, without the `+`, `-only` or
+/// `-or-later` telling whether later versions may be chosen
+fn base_id(spdx_id: &str) -> &str {
+    spdx_id.trim_end_matches('+').trim_end_matches("-or-later").trim_end_matches("-only")
+}
+
+/// Whether two SPDX identifiers refer to the same license, such as
+/// `GPL-3.0` and `gpl-3.0-or-later`
+pub fn same_license(a: &str, b: &str) -> bool {
+    base_id(a).eq_ignore_ascii_case(base_id(b))
 }
 
-#[derive(Debug, Serialize)]
-pub enum LicenseSource {
+/// Where a license file was found
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LicenseOrigin {
     Crate(String),
+    Git { url: String, commit: String, path: String },
     GitHubApi { url: String },
-    GitHubRepo { url: String },
+    GitHubRepo { url: String, git_ref: String },
+    ForgeRepo { forge: String, url: String, git_ref: String },
+}
+
+impl LicenseOrigin {
+    /// A short name for the kind of place the license file was found, as
+    /// given in the `kind` of origins in the JSON report
+    pub fn kind(&self) -> &'static str {
+        use self::LicenseOrigin::*;
+        match self {
+            &Crate(_) => "crate",
+            &Git { .. } => "git",
+            &GitHubApi { .. } => "github-api",
+            &GitHubRepo { .. } => "github-repo",
+            &ForgeRepo { .. } => "forge-repo",
+        }
+    }
+
+    /// Where the license file is, as a path or URL. Files in git checkouts
+    /// are given as `URL#COMMIT:PATH`.
+    pub fn location(&self) -> String {
+        use self::LicenseOrigin::*;
+        match self {
+            &Crate(ref path) => path.clone(),
+            &Git { ref url, ref commit, ref path } => format!("{}#{}:{}", url, commit, path),
+            &GitHubApi { ref url } | &GitHubRepo { ref url, .. } | &ForgeRepo { ref url, .. } => url.clone(),
+        }
+    }
+}
+
+/// A license file as it was retrieved, so that a stored attribution can
+/// later be verified against the original
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LicenseSource {
+    pub origin: LicenseOrigin,
+
+    /// SHA-256 of the license text, in lowercase hex
+    pub sha256: String,
+
+    /// When the license text was retrieved, as an RFC 3339 timestamp
+    pub fetched_at: String,
+}
+
+impl LicenseSource {
+    /// Records `license_text` as retrieved from `origin` just now
+    pub fn new(origin: LicenseOrigin, license_text: &str) -> LicenseSource {
+        LicenseSource {
+            origin,
+            sha256: sha256_hex(license_text),
+            fetched_at: timestamp::now(),
+        }
+    }
+}
+
+pub fn sha256_hex<T: AsRef<[u8]>>(data: T) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data.as_ref());
+
+    hasher.finish().iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+/// Evidence turned up while hounding a license that deserves a closer look
+/// by a human, recorded in the report alongside the conclusion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Finding {
+    /// A license detection service, such as the GitHub license API,
+    /// identified a different license than the one chosen from the crate
+    /// manifest. This may mean that the project has been relicensed or that
+    /// the manifest is wrong.
+    LicenseMismatch {
+        service: String,
+        url: String,
+        detected_license: String,
+        chosen_license: LicenseId,
+        license_text: String,
+    },
+}
+
+impl Finding {
+    /// The name of the variant, for summarizing findings by kind
+    pub fn kind(&self) -> &'static str {
+        match self {
+            &Finding::LicenseMismatch { .. } => "LicenseMismatch",
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn can_hash_license_text() {
+        assert_eq!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            sha256_hex("abc")
+        );
+    }
+
+    #[test]
+    fn can_categorize_licenses() {
+        assert_eq!(Category::Permissive, LicenseId::Mit.category());
+        assert_eq!(Category::WeakCopyleft, LicenseId::Mpl2.category());
+        assert_eq!(Category::StrongCopyleft, Category::of("GPL-3.0-or-later", None));
+        assert_eq!(Category::StrongCopyleft, Category::of("gpl-2.0+", None));
+        assert_eq!(Category::WeakCopyleft, Category::of("GPL-2.0-only", Some("Classpath-exception-2.0")));
+        assert_eq!(Category::NetworkCopyleft, Category::of("AGPL-3.0-only", None));
+        assert_eq!(Category::NonCommercial, Category::of("CC-BY-NC-4.0", None));
+        assert_eq!(Category::Proprietary, Category::of("LicenseRef-Proprietary", None));
+    }
+
+    #[test]
+    fn can_parse_spdx_ids() {
+        assert_eq!(Some(LicenseId::Bsd3Clause), LicenseId::from_spdx_id("BSD-3-Clause"));
+        assert_eq!(None, LicenseId::from_spdx_id("Bsd3Clause"));
+    }
 }
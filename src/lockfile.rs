@@ -1,6 +1,7 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::io;
-use std::path::Path;
+use std::str::FromStr;
 
 use toml;
 
@@ -22,28 +23,156 @@ impl From<toml::de::Error> for Error {
     }
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Error::Io(ref err) => write!(f, "{}", err),
+            &Error::Deserializing(ref err) => write!(f, "Not a valid lock file: {}", err),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Package {
     pub name: String,
     pub version: String,
     pub source: Option<String>,
+
+    /// SHA-256 of the `.crate` file, in newer lock files
+    pub checksum: Option<String>,
+
+    /// As `name`, `name version` or `name version (source)`, depending on
+    /// what is needed to tell the dependency apart from other packages
+    #[serde(default)]
+    pub dependencies: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct LockFile {
     pub package: Vec<Package>,
+
+    /// Holds the checksums in older lock files
+    #[serde(default)]
     pub metadata: HashMap<String, String>,
 }
 
+impl FromStr for LockFile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<LockFile, Error> {
+        Ok(toml::from_str(s)?)
+    }
+}
+
 impl LockFile {
-    pub fn from_file<P: AsRef<Path>>(f: P) -> Result<LockFile, Error> {
-        use std::fs::File;
-        use std::io::Read;
+    /// SHA-256 of the `.crate` file of `package`, for packages from
+    /// registries
+    pub fn checksum_of(&self, package: &Package) -> Option<String> {
+        if let Some(ref checksum) = package.checksum {
+            return Some(checksum.clone());
+        }
+
+        let key = format!("checksum {} {} ({})", package.name, package.version, try_opt!(package.source.as_ref()));
+        self.metadata.get(&key)
+            .filter(|x| *x != "<none>")
+            .cloned()
+    }
+
+    /// The packages `package` depends on directly
+    pub fn dependencies_of(&self, package: &Package) -> Vec<&Package> {
+        package.dependencies.iter()
+            .filter_map(|dependency| {
+                let mut parts = dependency.splitn(3, ' ');
+                let name = parts.next().unwrap_or("");
+                let version = parts.next();
+                let source = parts.next().map(|x| x.trim_matches(|c| c == '(' || c == ')'));
+
+                self.package.iter().find(|x|
+                    x.name == name &&
+                    version.map(|v| x.version == v).unwrap_or(true) &&
+                    source.map(|s| x.source.as_ref().map(|x| &x[..]) == Some(s)).unwrap_or(true)
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CRATES_IO: &str = "registry+https://github.com/rust-lang/crates.io-index";
+
+    fn names_and_versions(packages: Vec<&Package>) -> Vec<(&str, &str)> {
+        packages.into_iter().map(|x| (&x.name[..], &x.version[..])).collect()
+    }
+
+    #[test]
+    fn can_read_old_lock_files() {
+        let lock_file: LockFile = toml::from_str(r#"
+            [[package]]
+            name = "app"
+            version = "0.1.0"
+            dependencies = [
+             "a 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+             "a 2.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+            ]
+
+            [[package]]
+            name = "a"
+            version = "1.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+
+            [[package]]
+            name = "a"
+            version = "2.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+
+            [metadata]
+            "checksum a 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)" = "abc"
+            "checksum a 2.0.0 (registry+https://github.com/rust-lang/crates.io-index)" = "<none>"
+        "#).unwrap();
+
+        assert_eq!(vec![("a", "1.0.0"), ("a", "2.0.0")], names_and_versions(lock_file.dependencies_of(&lock_file.package[0])));
+        assert_eq!(None, lock_file.checksum_of(&lock_file.package[0]));
+        assert_eq!(Some("abc".to_string()), lock_file.checksum_of(&lock_file.package[1]));
+        assert_eq!(None, lock_file.checksum_of(&lock_file.package[2]));
+        assert_eq!(Some(CRATES_IO), lock_file.package[1].source.as_ref().map(|x| &x[..]));
+    }
+
+    #[test]
+    fn can_read_new_lock_files() {
+        let lock_file: LockFile = toml::from_str(r#"
+            version = 3
+
+            [[package]]
+            name = "app"
+            version = "0.1.0"
+            dependencies = [
+             "a 2.0.0",
+             "b",
+            ]
+
+            [[package]]
+            name = "a"
+            version = "1.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+            checksum = "abc"
+
+            [[package]]
+            name = "a"
+            version = "2.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+            checksum = "def"
 
-        let mut reader = io::BufReader::new(File::open(f)?);
-        let mut buf = String::new();
-        reader.read_to_string(&mut buf)?;
+            [[package]]
+            name = "b"
+            version = "0.1.0"
+            source = "git+https://github.com/owner/b#0123abc"
+        "#).unwrap();
 
-        Ok(toml::from_str(&buf)?)
+        assert_eq!(vec![("a", "2.0.0"), ("b", "0.1.0")], names_and_versions(lock_file.dependencies_of(&lock_file.package[0])));
+        assert_eq!(Some("def".to_string()), lock_file.checksum_of(&lock_file.package[2]));
+        assert_eq!(None, lock_file.checksum_of(&lock_file.package[3]));
     }
 }
@@ -1,27 +1,108 @@
+#[macro_use] extern crate handlebars;
 #[macro_use] extern crate itertools;
 #[macro_use] extern crate lazy_static;
 #[macro_use] extern crate serde_derive;
+#[macro_use] extern crate serde_json;
 #[macro_use] extern crate try_opt;
+extern crate atty;
 extern crate base64;
 extern crate cargo;
+extern crate docopt;
+extern crate flate2;
 extern crate regex;
 extern crate reqwest;
-extern crate serde_json;
 extern crate serde;
+extern crate tar;
+extern crate termcolor;
 extern crate toml;
 
-mod github;
+#[cfg(test)] extern crate jsonschema;
+#[cfg(test)] extern crate tempdir;
+
+mod cache;
+mod check;
+mod config;
+mod diff;
+mod download;
+mod expression;
+mod forge;
+mod http;
 mod license;
 mod lockfile;
+mod output;
+mod registry;
+mod report;
+mod timestamp;
+mod vcs_info;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 use cargo::core::{Source, SourceId, PackageId};
 use cargo::util::Config;
 use cargo::sources::SourceConfigMap;
+use docopt::Docopt;
 
+use forge::Revalidation;
 use license::*;
 
+const USAGE: &str = "
+Sniff out the licenses of all crate dependencies
+
+Usage:
+    cargo license-hound [options]
+    cargo license-hound render [options] <report>
+    cargo license-hound diff [options] <old> <new>
+    cargo license-hound check [options] [<report>]
+    cargo license-hound cache (list | clear)
+    cargo license-hound (-h | --help)
+
+Options:
+    -h, --help       Show this message
+    --format FORMAT  Output format: human, json, notices,
+                     notices-markdown, html, template, spdx-json,
+                     spdx-tag-value, cyclonedx-json, cyclonedx-xml, dep5,
+                     csv or tsv [default: human]
+    --title TITLE    Title of notices, HTML and template output, and
+                     name of SPDX documents and DEP-5 files
+    --css FILE       Stylesheet to embed in HTML output
+    --template FILE  Handlebars template for template output
+    --no-cache       Neither read nor write the cache of license files
+                     retrieved from forges
+    --annotations FORMAT  Also write the policy violations found by check
+                     for CI: github, junit or sarif
+
+The render command writes a report saved with --format json in another
+format, without hounding the licenses again.
+
+The diff command lists the license changes between two reports or lock
+files. Each of <old> and <new> is a path, a git object such as
+main:Cargo.lock, or a git revision, meaning ./Cargo.lock as of that revision.
+
+The check command holds the crates of Cargo.lock, or of a saved report, to
+the policy in license-hound.toml. It exits with 1 if the policy is violated,
+and with 2 if the check could not be carried out.
+";
+
+#[derive(Debug, Deserialize)]
+struct Args {
+    cmd_render: bool,
+    cmd_diff: bool,
+    cmd_check: bool,
+    cmd_cache: bool,
+    cmd_list: bool,
+    cmd_clear: bool,
+    flag_format: String,
+    flag_title: Option<String>,
+    flag_css: Option<String>,
+    flag_template: Option<String>,
+    flag_no_cache: bool,
+    flag_annotations: Option<String>,
+    arg_report: Option<String>,
+    arg_old: Option<String>,
+    arg_new: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 struct LicenseDescription {
     chosen_license: LicenseId,
@@ -39,17 +120,65 @@ enum LicenseError {
     UnableToRecoverLicenseFile(PathBuf),
     UnableToRecoverAttribution(String),
     UnacceptableLicense(String),
+    RegistryNotAudited(String),
+
+    /// A license detection service identified this license, and the
+    /// policy treats that as an error
+    LicenseMismatch(String),
+}
+
+impl LicenseError {
+    /// The name of the variant, for summarizing errors by kind
+    fn kind(&self) -> &'static str {
+        use LicenseError::*;
+        match self {
+            &NoSource => "NoSource",
+            &LicenseNotDeclared(_) => "LicenseNotDeclared",
+            &UnableToRecoverLicenseFile(_) => "UnableToRecoverLicenseFile",
+            &UnableToRecoverAttribution(_) => "UnableToRecoverAttribution",
+            &UnacceptableLicense(_) => "UnacceptableLicense",
+            &RegistryNotAudited(_) => "RegistryNotAudited",
+            &LicenseMismatch(_) => "LicenseMismatch",
+        }
+    }
+
+    /// What the error is about, if that fits on a line
+    fn detail(&self) -> Option<String> {
+        use LicenseError::*;
+        match self {
+            &NoSource | &UnableToRecoverAttribution(_) => None,
+            &LicenseNotDeclared(ref path) | &UnableToRecoverLicenseFile(ref path) => Some(path.display().to_string()),
+            &UnacceptableLicense(ref x) | &RegistryNotAudited(ref x) | &LicenseMismatch(ref x) => Some(x.clone()),
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
 struct LicenseReport {
     package_name: String,
     version: String,
+    registry: Option<String>,
+
+    /// As given in `Cargo.lock`. Absent for path dependencies
+    source: Option<String>,
+
+    /// SHA-256 of the `.crate` file, as given in `Cargo.lock`
+    checksum: Option<String>,
+
+    /// The direct dependencies, as `name version`
+    dependencies: Vec<String>,
+
+    findings: Vec<Finding>,
     conclusion: Result<LicenseDescription, LicenseError>,
 }
 
 struct LicenseHound<'a> {
     source_config_map: SourceConfigMap<'a>,
+    registries: registry::Registries,
+    client: Rc<dyn http::Client>,
+    forges: forge::Forges,
+    cache: Option<cache::Cache>,
+    hound_config: config::HoundConfig,
 }
 
 fn read_file<P: AsRef<std::path::Path>>(path: P) -> Result<String, std::io::Error> {
@@ -89,61 +218,229 @@ fn recover_copyright_notice(license_text: &str) -> Result<String, LicenseError>
     )
 }
 
+/// Git sources are checked out in their entirety, so the root of the
+/// checkout is the closest ancestor directory containing `.git`.
+fn find_checkout_root(package_root: &Path) -> Option<&Path> {
+    let mut dir = package_root;
+    while !dir.join(".git").exists() {
+        dir = try_opt!(dir.parent());
+    }
+    Some(dir)
+}
+
+/// Looks for a file named as `chosen_license` suggests in `dir`, giving the
+/// path of the file along with its contents
+fn license_file_in_dir(dir: &Path, chosen_license: LicenseId) -> Option<(PathBuf, String)> {
+    chosen_license.guess_filenames()
+        .map(|(a, b, c)| dir.join(format!("{}{}{}", a, b, c)))
+        .filter_map(|candidate| read_file(&candidate).ok().map(|license_text| (candidate, license_text)))
+        .next()
+}
+
+fn license_file_from_package(package_root: &Path, chosen_license: LicenseId) -> Option<(LicenseOrigin, String)> {
+    let (path, license_text) = try_opt!(license_file_in_dir(package_root, chosen_license));
+    let file_name = path.file_name().unwrap().to_string_lossy().into_owned();
+
+    Some((LicenseOrigin::Crate(file_name), license_text))
+}
+
+/// Looks for the license file of a crate checked out from the repository at
+/// `url`. Unless the locked `commit` and the root of the checkout are known,
+/// only the directory of the crate itself is searched.
+fn license_file_from_git_checkout(package_root: &Path, url: &str, commit: Option<&str>, chosen_license: LicenseId) -> Option<(LicenseOrigin, String)> {
+    let (commit, checkout_root) = match (commit, find_checkout_root(package_root)) {
+        (Some(commit), Some(checkout_root)) => (commit, checkout_root),
+        _ => return license_file_from_package(package_root, chosen_license),
+    };
+
+    // Crates in a monorepo frequently rely on a license file in the
+    // workspace root, so walk up from the crate to the root of the
+    // checkout, looking for candidates along the way.
+    let mut dir = package_root;
+    loop {
+        if let Some((candidate, license_text)) = license_file_in_dir(dir, chosen_license) {
+            let path = candidate.strip_prefix(checkout_root).unwrap()
+                .to_string_lossy()
+                .replace('\\', "/");
+
+            return Some((
+                LicenseOrigin::Git {
+                    url: url.to_string(),
+                    commit: commit.to_string(),
+                    path,
+                },
+                license_text,
+            ));
+        }
+
+        if dir == checkout_root {
+            return None;
+        }
+        dir = try_opt!(dir.parent());
+    }
+}
+
+fn store_in_cache(cache: &cache::Cache, entry: &cache::Entry) {
+    if let Err(err) = cache.put(entry) {
+        eprintln!("WARN Unable to cache the license file of {} {}: {:?}", entry.key.name, entry.key.version, err);
+    }
+}
+
+/// Gives the license file cached for `key`, along with the findings from
+/// when it was looked up. Cached files are revalidated if the forge gave an
+/// `ETag` for them, but kept if the forge cannot be reached.
+fn license_file_from_cache(cache: &cache::Cache, forges: &forge::Forges, key: &cache::Key, findings: &mut Vec<Finding>) -> Option<(LicenseSource, String)> {
+    let mut entry = try_opt!(cache.get(key));
+
+    let revalidation = match entry.etag {
+        Some(ref etag) => forges.revalidate(&entry.url, etag),
+        None => Revalidation::NotModified,
+    };
+
+    match revalidation {
+        Revalidation::NotModified | Revalidation::Unavailable => {},
+        Revalidation::Modified(file) => {
+            entry.license_source = LicenseSource::new(entry.license_source.origin, &file.contents);
+            entry.license_text = file.contents;
+            entry.etag = file.etag;
+            store_in_cache(cache, &entry);
+        },
+        Revalidation::Gone => return None,
+    }
+
+    findings.extend(entry.findings);
+    Some((entry.license_source, entry.license_text))
+}
+
+/// With `MismatchPolicy::Error`, a license detection service disagreeing
+/// with the crate manifest fails the crate
+fn enforce_mismatch_policy(policy: config::MismatchPolicy, findings: &[Finding]) -> Result<(), LicenseError> {
+    if policy != config::MismatchPolicy::Error {
+        return Ok(());
+    }
+
+    let detected_license = findings.iter()
+        .filter_map(|x| match *x {
+            Finding::LicenseMismatch { ref detected_license, .. } => Some(detected_license.clone()),
+        })
+        .next();
+
+    match detected_license {
+        Some(detected_license) => Err(LicenseError::LicenseMismatch(detected_license)),
+        None => Ok(()),
+    }
+}
+
 impl<'a> LicenseHound<'a> {
-    fn new(config: &'a Config) -> LicenseHound<'a> {
-        let source_config_map = SourceConfigMap::new(&config).unwrap();
+    fn new(config: &'a Config, hound_config: config::HoundConfig, cache: Option<cache::Cache>, client: Rc<dyn http::Client>) -> Result<LicenseHound<'a>, registry::Error> {
+        let source_config_map = SourceConfigMap::new(&config)?;
+        let registries = registry::Registries::from_config(&config)?;
+        let forges = forge::Forges::from_config(&hound_config, client.clone());
 
-        LicenseHound { source_config_map }
+        Ok(LicenseHound { source_config_map, registries, client, forges, cache, hound_config })
     }
 
-    fn license_file_from_package(&self, package: &cargo::core::Package, chosen_license: LicenseId) -> Option<(LicenseSource, String)> {
-        let manifest_path = package.manifest_path();
+    fn registry_of(&self, package: &lockfile::Package) -> Option<String> {
+        let source = try_opt!(package.source.as_ref());
+        let source_id = try_opt!(SourceId::from_url(&source).ok());
 
-        for (a, b, c) in chosen_license.guess_filenames() {
-            let candidate_name = format!("{}{}{}", a, b, c);
+        self.registries.name_of(&source_id)
+    }
+
+    fn store_in_cache(&self, entry: &cache::Entry) {
+        if let Some(ref cache) = self.cache {
+            store_in_cache(cache, entry);
+        }
+    }
 
-            if let Ok(license_text) = read_file(manifest_path.with_file_name(&candidate_name)) {
-                return Some((LicenseSource::Crate(candidate_name), license_text));
+    /// Looks for the license file on forges, consulting the cache first
+    fn license_file_from_forges(&self, package: &cargo::core::Package, source_id: &SourceId, chosen_license: LicenseId, cache_key: Option<&cache::Key>, findings: &mut Vec<Finding>) -> Option<(LicenseSource, String)> {
+        if let (Some(cache), Some(key)) = (self.cache.as_ref(), cache_key) {
+            if let Some(cached) = license_file_from_cache(cache, &self.forges, key, findings) {
+                return Some(cached);
             }
         }
 
-        None
+        let (origin, file) = try_opt!(self.forges.license_file(package, source_id, chosen_license, findings));
+        let license_source = LicenseSource::new(origin, &file.contents);
+
+        if let Some(key) = cache_key {
+            self.store_in_cache(&cache::Entry {
+                key: key.clone(),
+                license_source: license_source.clone(),
+                url: file.url,
+                etag: file.etag,
+                license_text: file.contents.clone(),
+                findings: findings.clone(),
+            });
+        }
+
+        Some((license_source, file.contents))
     }
 
-    fn hound_license_file(&self, package: &cargo::core::Package, chosen_license: LicenseId) -> Result<(LicenseSource, String), LicenseError> {
-        self.license_file_from_package(package, chosen_license)
-            .or_else(|| github::license_file_from_github(package, chosen_license))
+    fn hound_license_file(&self, package: &cargo::core::Package, source_id: &SourceId, chosen_license: LicenseId, cache_key: Option<&cache::Key>, findings: &mut Vec<Finding>) -> Result<(LicenseSource, String), LicenseError> {
+        let local =
+            if source_id.is_git() {
+                license_file_from_git_checkout(package.root(), source_id.url().as_str(), source_id.precise(), chosen_license)
+            } else {
+                license_file_from_package(package.root(), chosen_license)
+            };
+
+        local
+            .map(|(origin, license_text)| (LicenseSource::new(origin, &license_text), license_text))
+            .or_else(|| self.license_file_from_forges(package, source_id, chosen_license, cache_key, findings))
             .ok_or_else(|| LicenseError::UnableToRecoverLicenseFile(package.manifest_path().with_file_name("").to_owned()))
     }
 
-    fn chase(&self, package: &lockfile::Package) -> Result<LicenseDescription, LicenseError> {
-        let source = package.source.as_ref().ok_or(LicenseError::NoSource)?;
+    /// Cargo does not send tokens when downloading crates, so crates from
+    /// registries that require one are downloaded by license-hound itself
+    fn download_with_token(&self, source_id: &SourceId, locked: &lockfile::Package, checksum: Option<&str>, token: &str) -> cargo::core::Package {
+        let config = self.source_config_map.config();
 
+        let dir = download::download(config, &*self.client, source_id, &locked.name, &locked.version, token, checksum)
+            .unwrap_or_else(|err| {
+                eprintln!("ERROR Unable to download {} {}: {}", locked.name, locked.version, err);
+                std::process::exit(1);
+            });
+
+        cargo::ops::read_package(&dir.join("Cargo.toml"), source_id, config).unwrap().0
+    }
+
+    /// Evidence that turns up along the way is added to `findings`
+    fn chase(&self, locked: &lockfile::Package, checksum: Option<&str>, findings: &mut Vec<Finding>) -> Result<LicenseDescription, LicenseError> {
+        let source = locked.source.as_ref().ok_or(LicenseError::NoSource)?;
         let source_id = SourceId::from_url(&source).unwrap();
+
+        if let Some(registry) = self.registries.name_of(&source_id) {
+            if !self.hound_config.audits_registry(&registry) {
+                return Err(LicenseError::RegistryNotAudited(registry));
+            }
+        }
+
         let mut source = self.source_config_map.load(&source_id).unwrap();
         source.update().unwrap();
 
-        let package_id = PackageId::new(&package.name, &package.version, &source_id).unwrap();
-        let package = source.download(&package_id).unwrap();
+        let package = match self.registries.token_of(&source_id) {
+            Some(token) => self.download_with_token(&source_id, locked, checksum, token),
+            None => {
+                let package_id = PackageId::new(&locked.name, &locked.version, &source_id).unwrap();
+                source.download(&package_id).unwrap()
+            },
+        };
         let metadata = package.manifest().metadata();
 
         let spdx_license = metadata.license.as_ref().ok_or(LicenseError::LicenseNotDeclared(package.manifest_path().to_owned()))?;
 
-        // YOLO! This will give legally wrong results for descriptors such as "MIT AND GPL3",
-        // which I have never seen in the wild. The more robust solution here is to implement
-        // a proper parser for the spdx syntax and implement boolean logic for it.
-        let chosen_license =
-            if spdx_license.find("MIT").is_some() {
-                Ok(LicenseId::Mit)
-            } else if spdx_license.find("MPL-2.0").is_some() {
-                Ok(LicenseId::Mpl2)
-            } else if spdx_license.find("BSD-3-Clause").is_some() {
-                Ok(LicenseId::Bsd3Clause)
-            } else {
-                Err(LicenseError::UnacceptableLicense(spdx_license.clone()))
-            }?;
+        let chosen_license = expression::parse(spdx_license)
+            .and_then(|declared| check::choose_license(&self.hound_config.policy, &declared))
+            .ok_or_else(|| LicenseError::UnacceptableLicense(spdx_license.clone()))?;
+
+        let cache_key = cache::Key::of(locked, chosen_license);
+        let hounded = self.hound_license_file(&package, &source_id, chosen_license, cache_key.as_ref(), findings);
+
+        enforce_mismatch_policy(self.hound_config.policy.license_mismatch, findings)?;
 
-        let (license_source, full_license_document) = self.hound_license_file(&package, chosen_license)?;
+        let (license_source, full_license_document) = hounded?;
 
         let copyright_notice = recover_copyright_notice(&full_license_document)?;
 
@@ -160,20 +457,437 @@ impl<'a> LicenseHound<'a> {
                 .map(|x| x.to_string()),
         })
     }
+
+    /// Hounds the licenses of all packages in a lock file with the given
+    /// contents
+    fn hound_lock_file(&self, lock_file_text: &str) -> Result<report::Report, lockfile::Error> {
+        let lock_file: lockfile::LockFile = lock_file_text.parse()?;
+
+        let license_reports =
+            lock_file.package.iter().map(|x| {
+                let registry = self.registry_of(x);
+                let checksum = lock_file.checksum_of(x);
+                let mut findings = vec![];
+                let conclusion = self.chase(x, checksum.as_ref().map(String::as_str), &mut findings);
+                LicenseReport {
+                    package_name: x.name.clone(),
+                    version: x.version.clone(),
+                    registry,
+                    source: x.source.clone(),
+                    checksum,
+                    dependencies: lock_file.dependencies_of(x).into_iter()
+                        .map(|dependency| format!("{} {}", dependency.name, dependency.version))
+                        .collect(),
+                    findings,
+                    conclusion,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Ok(report::Report::new(lock_file_text, license_reports))
+    }
+}
+
+fn cache_command(args: &Args, cache: &cache::Cache) {
+    if args.cmd_list {
+        let entries = cache.entries().unwrap_or_else(|err| {
+            eprintln!("ERROR Unable to read the cache in {}: {:?}", cache.dir().display(), err);
+            std::process::exit(1);
+        });
+
+        for entry in entries {
+            println!("{} {} {} ({})", entry.key.name, entry.key.version, entry.key.license.spdx_id(), entry.key.source);
+            match entry.etag {
+                Some(etag) => println!("    {} {}", entry.url, etag),
+                None => println!("    {}", entry.url),
+            }
+        }
+    } else if args.cmd_clear {
+        let count = cache.clear().unwrap_or_else(|err| {
+            eprintln!("ERROR Unable to clear the cache in {}: {:?}", cache.dir().display(), err);
+            std::process::exit(1);
+        });
+        eprintln!("Removed {} cached license files from {}", count, cache.dir().display());
+    }
+}
+
+fn read_report(path: &str) -> Result<report::Report, String> {
+    let json = read_file(path).map_err(|err| err.to_string())?;
+    report::Report::from_json(&json).map_err(|err| err.to_string())
+}
+
+fn write_output(format: output::Format, report: &report::Report, output_options: &output::Options) {
+    output::write(format, report, output_options).unwrap_or_else(|err| {
+        eprintln!("ERROR Unable to write the report: {}", err);
+        std::process::exit(1);
+    });
+}
+
+fn render_command(args: &Args, format: output::Format, output_options: &output::Options) {
+    let path = args.arg_report.as_ref().expect("The render command requires a report");
+
+    let report = read_report(path).unwrap_or_else(|err| {
+        eprintln!("ERROR Unable to read the report {}: {}", path, err);
+        std::process::exit(1);
+    });
+
+    write_output(format, &report, output_options);
+}
+
+fn new_license_hound<'a>(config: &'a Config, hound_config: config::HoundConfig, args: &Args, exit_code: i32) -> LicenseHound<'a> {
+    let cache =
+        if args.flag_no_cache {
+            None
+        } else {
+            Some(cache::Cache::new(cache::Cache::default_dir(config)))
+        };
+
+    LicenseHound::new(config, hound_config, cache, Rc::new(http::ReqwestClient)).unwrap_or_else(|err| {
+        eprintln!("ERROR {}", err);
+        std::process::exit(exit_code);
+    })
+}
+
+fn warn_about_skipped_lookups(license_hound: &LicenseHound) {
+    let skipped_lookups = license_hound.forges.skipped_lookups();
+    if skipped_lookups > 0 {
+        eprintln!(
+            "WARN {} remote lookups were skipped due to rate limiting. \
+            Licenses reported as unrecoverable may be found on a later run",
+            skipped_lookups
+        );
+    }
+}
+
+fn read_lock_file_or_exit(exit_code: i32) -> String {
+    read_file("Cargo.lock").unwrap_or_else(|err| {
+        eprintln!("ERROR Unable to read Cargo.lock: {}", err);
+        std::process::exit(exit_code);
+    })
+}
+
+fn hound_config_or_exit(exit_code: i32) -> config::HoundConfig {
+    config::HoundConfig::from_file(config::CONFIG_FILE).unwrap_or_else(|err| {
+        eprintln!("ERROR Unable to read {}: {}", config::CONFIG_FILE, err);
+        std::process::exit(exit_code);
+    })
+}
+
+fn hound_or_exit(license_hound: &LicenseHound, lock_file_text: &str, exit_code: i32) -> report::Report {
+    license_hound.hound_lock_file(lock_file_text).unwrap_or_else(|err| {
+        eprintln!("ERROR Unable to read the lock file: {}", err);
+        std::process::exit(exit_code);
+    })
+}
+
+fn diff_command(args: &Args) {
+    let read = |spec: &Option<String>| {
+        let spec = spec.as_ref().expect("The diff command requires two inputs");
+        diff::Input::read(spec).unwrap_or_else(|err| {
+            eprintln!("ERROR Unable to read {}: {}", spec, err);
+            std::process::exit(1);
+        })
+    };
+
+    let (old, new) = match (read(&args.arg_old), read(&args.arg_new)) {
+        (diff::Input::Report(old), diff::Input::Report(new)) => (old, new),
+        (old, new) => {
+            // Reports are compared as they are, so Cargo is only needed
+            // when hounding the licenses of a lock file
+            let hound_config = hound_config_or_exit(1);
+            let config = Config::default().unwrap();
+            let license_hound = new_license_hound(&config, hound_config, args, 1);
+
+            let hound = |input| match input {
+                diff::Input::Report(report) => report,
+                diff::Input::LockFile(lock_file_text) => hound_or_exit(&license_hound, &lock_file_text, 1),
+            };
+            let reports = (hound(old), hound(new));
+
+            warn_about_skipped_lookups(&license_hound);
+            reports
+        },
+    };
+
+    diff::write(&mut std::io::stdout(), &diff::compare(&old.crates, &new.crates)).unwrap();
+}
+
+fn check_command(args: &Args) {
+    let annotations = args.flag_annotations.as_ref().map(|x| {
+        x.parse::<check::Annotations>().unwrap_or_else(|err| {
+            eprintln!("ERROR {}", err);
+            std::process::exit(2);
+        })
+    });
+
+    let hound_config = hound_config_or_exit(2);
+    let policy = hound_config.policy.clone();
+
+    let report = match args.arg_report {
+        Some(ref path) => read_report(path).unwrap_or_else(|err| {
+            eprintln!("ERROR Unable to read the report {}: {}", path, err);
+            std::process::exit(2);
+        }),
+        None => {
+            let config = Config::default().unwrap();
+            let license_hound = new_license_hound(&config, hound_config, args, 2);
+            let report = hound_or_exit(&license_hound, &read_lock_file_or_exit(2), 2);
+            warn_about_skipped_lookups(&license_hound);
+            report
+        },
+    };
+
+    let violations = check::violations(&policy, &report.crates);
+
+    check::write_summary(&mut std::io::stderr(), &violations).unwrap();
+    if let Some(annotations) = annotations {
+        check::write_annotations(&mut std::io::stdout(), annotations, &report.crates, &violations).unwrap();
+    }
+
+    if !violations.is_empty() {
+        std::process::exit(1);
+    }
 }
 
 fn main() {
-    let config = Config::default().unwrap();
-    let license_hound = LicenseHound::new(&config);
+    let args: Args = Docopt::new(USAGE)
+        .and_then(|d| d.deserialize())
+        .unwrap_or_else(|e| e.exit());
+
+    let format: output::Format = args.flag_format.parse().unwrap_or_else(|err| {
+        eprintln!("ERROR {}", err);
+        std::process::exit(1);
+    });
+
+    if format == output::Format::Template && args.flag_template.is_none() {
+        eprintln!("ERROR --format template requires --template FILE");
+        std::process::exit(1);
+    }
 
-    let packages = lockfile::LockFile::from_file("Cargo.lock").unwrap().package;
+    let template = args.flag_template.as_ref().map(|path| {
+        output::Template::from_file(path).unwrap_or_else(|err| {
+            eprintln!("ERROR Unable to load the template {}: {}", path, err);
+            std::process::exit(1);
+        })
+    });
 
-    let license_reports =
-        packages.into_iter().map(|x| {
-            let conclusion = license_hound.chase(&x);
-            LicenseReport { package_name: x.name, version: x.version, conclusion }
+    let stylesheet = args.flag_css.as_ref().map(|path| {
+        read_file(path).unwrap_or_else(|err| {
+            eprintln!("ERROR Unable to read the stylesheet {}: {}", path, err);
+            std::process::exit(1);
         })
-        .collect::<Vec<_>>();
+    });
+
+    let output_options = output::Options {
+        title: args.flag_title.clone(),
+        stylesheet,
+        template,
+    };
+
+    if args.cmd_render {
+        return render_command(&args, format, &output_options);
+    }
+
+    if args.cmd_diff {
+        return diff_command(&args);
+    }
+
+    if args.cmd_check {
+        return check_command(&args);
+    }
+
+    let config = Config::default().unwrap();
+
+    if args.cmd_cache {
+        return cache_command(&args, &cache::Cache::new(cache::Cache::default_dir(&config)));
+    }
+
+    let hound_config = hound_config_or_exit(1);
+    let license_hound = new_license_hound(&config, hound_config, &args, 1);
+
+    let report = hound_or_exit(&license_hound, &read_lock_file_or_exit(1), 1);
+    write_output(format, &report, &output_options);
+
+    warn_about_skipped_lookups(&license_hound);
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use tempdir::TempDir;
+
+    use super::*;
+    use forge::github::{Auth, GitHub};
+    use http::mock::MockClient;
 
-    serde_json::to_writer(std::io::stdout(), &license_reports).unwrap();
+    const URL: &str = "https://github.com/owner/repo";
+    const COMMIT: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn origin_path(found: Option<(LicenseOrigin, String)>) -> String {
+        match found {
+            Some((LicenseOrigin::Git { url, commit, path }, _)) => {
+                assert_eq!(URL, url);
+                assert_eq!(COMMIT, commit);
+                path
+            },
+            x => panic!("Unexpected license file {:?}", x),
+        }
+    }
+
+    #[test]
+    fn finds_license_file_at_checkout_root() {
+        let checkout = TempDir::new("cargo-license-hound-checkout").unwrap();
+        let root = checkout.path();
+        let package_root = root.join("crates").join("package");
+
+        fs::create_dir(root.join(".git")).unwrap();
+        write(&package_root.join("Cargo.toml"), "");
+        write(&root.join("LICENSE-MIT"), "Copyright (c) 2017 Someone");
+
+        let found = license_file_from_git_checkout(&package_root, URL, Some(COMMIT), LicenseId::Mit);
+        assert_eq!("LICENSE-MIT", origin_path(found));
+
+        write(&package_root.join("LICENSE"), "Copyright (c) 2017 Someone");
+
+        let found = license_file_from_git_checkout(&package_root, URL, Some(COMMIT), LicenseId::Mit);
+        assert_eq!("crates/package/LICENSE", origin_path(found));
+    }
+
+    #[test]
+    fn stops_at_checkout_root() {
+        let dir = TempDir::new("cargo-license-hound-checkout").unwrap();
+        let root = dir.path().join("checkout");
+        let package_root = root.join("package");
+
+        fs::create_dir_all(root.join(".git")).unwrap();
+        write(&package_root.join("Cargo.toml"), "");
+        write(&dir.path().join("LICENSE"), "Copyright (c) 2017 Someone else");
+
+        assert!(license_file_from_git_checkout(&package_root, URL, Some(COMMIT), LicenseId::Mit).is_none());
+    }
+
+    #[test]
+    fn falls_back_to_crate_directory_outside_of_checkout() {
+        let dir = TempDir::new("cargo-license-hound-checkout").unwrap();
+        let package_root = dir.path().join("package");
+
+        write(&package_root.join("LICENSE"), "Copyright (c) 2017 Someone");
+
+        for &commit in &[Some(COMMIT), None] {
+            match license_file_from_git_checkout(&package_root, URL, commit, LicenseId::Mit) {
+                Some((LicenseOrigin::Crate(file_name), license_text)) => {
+                    assert_eq!("LICENSE", file_name);
+                    assert_eq!("Copyright (c) 2017 Someone", license_text);
+                },
+                x => panic!("Unexpected license file {:?}", x),
+            }
+        }
+    }
+
+    const LICENSE_URL: &str = "https://raw.githubusercontent.com/owner/repo/master/LICENSE";
+
+    fn mismatch() -> Finding {
+        Finding::LicenseMismatch {
+            service: "github".to_string(),
+            url: LICENSE_URL.to_string(),
+            detected_license: "Apache-2.0".to_string(),
+            chosen_license: LicenseId::Mit,
+            license_text: "Apache License".to_string(),
+        }
+    }
+
+    fn github(client: MockClient) -> forge::Forges {
+        forge::Forges::new(vec![Box::new(GitHub::new(
+            "https://github.com",
+            "https://api.github.com",
+            "https://raw.githubusercontent.com",
+            Auth::Anonymous,
+        ))], Rc::new(client))
+    }
+
+    fn cached(key: cache::Key) -> cache::Entry {
+        cache::Entry {
+            key,
+            license_source: LicenseSource::new(
+                LicenseOrigin::GitHubRepo { url: LICENSE_URL.to_string(), git_ref: "master".to_string() },
+                "Copyright (c) 2017 Someone",
+            ),
+            url: LICENSE_URL.to_string(),
+            etag: None,
+            license_text: "Copyright (c) 2017 Someone".to_string(),
+            findings: vec![],
+        }
+    }
+
+    #[test]
+    fn mismatch_policy_error_fails_the_crate() {
+        assert!(enforce_mismatch_policy(config::MismatchPolicy::Warn, &[mismatch()]).is_ok());
+        assert!(enforce_mismatch_policy(config::MismatchPolicy::Error, &[]).is_ok());
+
+        match enforce_mismatch_policy(config::MismatchPolicy::Error, &[mismatch()]) {
+            Err(LicenseError::LicenseMismatch(detected_license)) => assert_eq!("Apache-2.0", detected_license),
+            x => panic!("Unexpected result {:?}", x),
+        }
+    }
+
+    #[test]
+    fn mismatch_policy_applies_to_cached_findings() {
+        let dir = TempDir::new("cargo-license-hound-cache").unwrap();
+        let cache = cache::Cache::new(dir.path());
+
+        let key = cache::Key {
+            name: "package".to_string(),
+            version: "1.0.0".to_string(),
+            source: "registry+https://github.com/rust-lang/crates.io-index".to_string(),
+            license: LicenseId::Mit,
+        };
+
+        cache.put(&cache::Entry {
+            etag: Some("\"abc\"".to_string()),
+            findings: vec![mismatch()],
+            ..cached(key.clone())
+        }).unwrap();
+
+        let forges = github(MockClient::new(&[(LICENSE_URL, 304, "")]));
+
+        let mut findings = vec![];
+        let (_, license_text) = license_file_from_cache(&cache, &forges, &key, &mut findings).unwrap();
+        assert_eq!("Copyright (c) 2017 Someone", license_text);
+
+        match enforce_mismatch_policy(config::MismatchPolicy::Error, &findings) {
+            Err(LicenseError::LicenseMismatch(detected_license)) => assert_eq!("Apache-2.0", detected_license),
+            x => panic!("Unexpected result {:?}", x),
+        }
+    }
+
+    #[test]
+    fn cached_license_files_depend_on_the_policy() {
+        let dir = TempDir::new("cargo-license-hound-cache").unwrap();
+        let cache = cache::Cache::new(dir.path());
+
+        let locked = lockfile::Package {
+            name: "package".to_string(),
+            version: "1.0.0".to_string(),
+            source: Some("registry+https://github.com/rust-lang/crates.io-index".to_string()),
+            checksum: None,
+            dependencies: vec![],
+        };
+        let declared = expression::parse("MIT OR Apache-2.0").unwrap();
+        let key_for = |policy: &config::Policy| cache::Key::of(&locked, check::choose_license(policy, &declared).unwrap()).unwrap();
+
+        let lenient = key_for(&config::Policy::default());
+        cache.put(&cached(lenient.clone())).unwrap();
+
+        let forges = github(MockClient::new(&[]));
+        assert!(license_file_from_cache(&cache, &forges, &lenient, &mut vec![]).is_some());
+
+        let apache_only = key_for(&config::Policy { allow: vec!["Apache-2.0".to_string()], ..config::Policy::default() });
+        assert!(license_file_from_cache(&cache, &forges, &apache_only, &mut vec![]).is_none());
+    }
 }
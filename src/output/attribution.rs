@@ -0,0 +1,114 @@
+//! The reports arranged for attribution documents, with crates grouped by
+//! identical license text so each text only needs to be reproduced once
+
+use std::collections::BTreeMap;
+
+use license::LicenseId;
+use {LicenseError, LicenseReport};
+
+pub const DEFAULT_TITLE: &str = "Third-party software notices";
+
+pub const INTRODUCTION: &str = "This software includes the following third-party crates, \
+    which are distributed under the licenses given below.";
+
+/// A crate to be credited
+#[derive(Serialize)]
+pub struct Attribution<'a> {
+    pub name: &'a str,
+    pub version: &'a str,
+    pub link: Option<&'a str>,
+    pub copyright_notice: &'a str,
+}
+
+/// Crates that share a license text
+pub struct Group<'a> {
+    pub license: LicenseId,
+    pub license_text: String,
+    pub crates: Vec<Attribution<'a>>,
+}
+
+/// Texts that differ only in line endings or trailing whitespace are
+/// considered identical
+fn normalize(license_text: &str) -> String {
+    license_text.lines()
+        .map(|x| x.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_matches('\n')
+        .to_string()
+}
+
+/// Groups the crates with a recovered license. The groups are ordered by
+/// license and then by the first crate in them, and the crates in each
+/// group by name and version.
+pub fn group(reports: &[LicenseReport]) -> Vec<Group> {
+    let mut groups = BTreeMap::new();
+
+    for report in reports {
+        let description = match report.conclusion {
+            Ok(ref description) => description,
+            Err(_) => continue,
+        };
+
+        let license_text = normalize(&description.full_license_document);
+        let key = (description.chosen_license.spdx_id(), license_text);
+
+        groups.entry(key).or_insert_with(|| (description.chosen_license, vec![])).1.push(Attribution {
+            name: &report.package_name,
+            version: &report.version,
+            link: description.link.as_ref().map(|x| &x[..]),
+            copyright_notice: &description.copyright_notice,
+        });
+    }
+
+    let mut groups = groups.into_iter()
+        .map(|((_, license_text), (license, mut crates))| {
+            crates.sort_by(|a, b| (a.name, a.version).cmp(&(b.name, b.version)));
+            Group { license, license_text, crates }
+        })
+        .collect::<Vec<_>>();
+
+    groups.sort_by(|a, b| (a.license.spdx_id(), a.crates[0].name).cmp(&(b.license.spdx_id(), b.crates[0].name)));
+
+    groups
+}
+
+/// The crates left out of attribution documents because their license
+/// could not be recovered. Crates without a source, such as the crates of
+/// the workspace itself, are not third-party crates and so not missing.
+/// Neither are crates from registries configured with `audit = false`,
+/// which are left out on purpose.
+pub fn missing(reports: &[LicenseReport]) -> Vec<&LicenseReport> {
+    reports.iter()
+        .filter(|x| match x.conclusion {
+            Ok(_) | Err(LicenseError::NoSource) | Err(LicenseError::RegistryNotAudited(_)) => false,
+            Err(_) => true,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use license::LicenseId;
+    use output::fixture;
+
+    #[test]
+    fn leaves_out_crates_not_meant_to_be_attributed() {
+        let mut internal = fixture::unrecoverable("internal");
+        internal.conclusion = Err(LicenseError::RegistryNotAudited("our-registry".to_string()));
+
+        let mut workspace = fixture::unrecoverable("workspace");
+        workspace.conclusion = Err(LicenseError::NoSource);
+
+        let reports = vec![
+            fixture::ok("a", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 A"),
+            fixture::unrecoverable("b"),
+            internal,
+            workspace,
+        ];
+
+        let missing = missing(&reports).iter().map(|x| &x.package_name[..]).collect::<Vec<_>>();
+        assert_eq!(vec!["b"], missing);
+    }
+}
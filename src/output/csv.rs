@@ -0,0 +1,163 @@
+//! One row per crate, as CSV or TSV, for reviewing in a spreadsheet
+//!
+//! The columns are always the same and in the same order, so successive
+//! exports can be compared.
+
+use std::io::{self, Write};
+
+use super::{sbom, Status};
+use LicenseReport;
+
+const COLUMNS: &[&str] = &[
+    "name",
+    "version",
+    "registry",
+    "declared_license",
+    "concluded_license",
+    "license_source",
+    "license_location",
+    "copyright_notice",
+    "link",
+    "status",
+    "detail",
+];
+
+fn row(report: &LicenseReport) -> Vec<String> {
+    let description = report.conclusion.as_ref().ok();
+    let status = Status::of(report);
+
+    let detail = match report.conclusion {
+        Err(ref err) => match err.detail() {
+            Some(detail) => format!("{}: {}", err.kind(), detail),
+            None => err.kind().to_string(),
+        },
+        Ok(_) => match status {
+            Status::Warn(ref findings) => findings.clone(),
+            _ => String::new(),
+        },
+    };
+
+    vec![
+        report.package_name.clone(),
+        report.version.clone(),
+        report.registry.clone().unwrap_or_default(),
+        sbom::declared_text(report).unwrap_or_default(),
+        sbom::concluded_license(report).unwrap_or_default().to_string(),
+        description.map(|x| x.license_source.origin.kind().to_string()).unwrap_or_default(),
+        description.map(|x| x.license_source.origin.location()).unwrap_or_default(),
+        sbom::copyright_notice(report).unwrap_or_default().to_string(),
+        description.and_then(|x| x.link.clone()).unwrap_or_default(),
+        status.label().to_string(),
+        detail,
+    ]
+}
+
+/// Values come from crate manifests and license files, which are not to be
+/// trusted. Spreadsheets run values starting with any of these as formulas.
+const FORMULA_PREFIXES: &[char] = &['=', '+', '-', '@', '\t', '\r'];
+
+/// Prefixes values that a spreadsheet would run as formulas with `'`, so
+/// they are shown as text
+fn text_value(value: &str) -> String {
+    if value.starts_with(FORMULA_PREFIXES) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Quotes fields as needed, as described in RFC 4180
+fn csv_field(value: &str) -> String {
+    let value = text_value(value);
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// TSV has no quoting, so tabs and line breaks are replaced by spaces
+fn tsv_field(value: &str) -> String {
+    text_value(value).replace(&['\t', '\n', '\r'][..], " ")
+}
+
+fn write_rows<W: Write>(out: &mut W, reports: &[LicenseReport], separator: &str, field: fn(&str) -> String) -> io::Result<()> {
+    let header = COLUMNS.iter().map(|x| field(x)).collect::<Vec<_>>();
+    write!(out, "{}\r\n", header.join(separator))?;
+
+    for report in reports {
+        let fields = row(report).iter().map(|x| field(x)).collect::<Vec<_>>();
+        write!(out, "{}\r\n", fields.join(separator))?;
+    }
+
+    Ok(())
+}
+
+pub fn write_csv<W: Write>(out: &mut W, reports: &[LicenseReport]) -> io::Result<()> {
+    write_rows(out, reports, ",", csv_field)
+}
+
+pub fn write_tsv<W: Write>(out: &mut W, reports: &[LicenseReport]) -> io::Result<()> {
+    write_rows(out, reports, "\t", tsv_field)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use license::LicenseId;
+    use output::fixture;
+
+    fn reports() -> Vec<LicenseReport> {
+        let mut reports = vec![
+            fixture::ok("a", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 \"A\", B\tand C"),
+            fixture::unrecoverable("b"),
+        ];
+        reports[0].conclusion.as_mut().unwrap().full_spdx_license = "MIT/Apache-2.0".to_string();
+        reports[0].conclusion.as_mut().unwrap().link = Some("https://example.com/a".to_string());
+        reports
+    }
+
+    #[test]
+    fn can_write_csv() {
+        let mut out = vec![];
+        write_csv(&mut out, &reports()).unwrap();
+
+        assert_eq!(
+            "name,version,registry,declared_license,concluded_license,license_source,license_location,copyright_notice,link,status,detail\r\n\
+            a,1.0.0,crates-io,MIT OR Apache-2.0,MIT,crate,LICENSE,\"Copyright (c) 2017 \"\"A\"\", B\tand C\",https://example.com/a,OK,\r\n\
+            b,1.0.0,crates-io,,,,,,,ERROR,UnableToRecoverLicenseFile: b/Cargo.toml\r\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+
+    #[test]
+    fn keeps_spreadsheets_from_running_formulas() {
+        let mut reports = reports();
+        reports[0].conclusion.as_mut().unwrap().copyright_notice = "=HYPERLINK(\"https://example.com\")".to_string();
+        reports[0].conclusion.as_mut().unwrap().link = Some("@SUM(A1)".to_string());
+
+        let mut out = vec![];
+        write_csv(&mut out, &reports).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(",\"'=HYPERLINK(\"\"https://example.com\"\")\",'@SUM(A1),OK,"), "{}", out);
+
+        assert_eq!("'-1", csv_field("-1"));
+        assert_eq!("'+1", csv_field("+1"));
+        assert_eq!("\"'\rx\"", csv_field("\rx"));
+        assert_eq!("' x", tsv_field("\tx"));
+        assert_eq!("MIT", csv_field("MIT"));
+    }
+
+    #[test]
+    fn can_write_tsv() {
+        let mut out = vec![];
+        write_tsv(&mut out, &reports()).unwrap();
+
+        let lines = String::from_utf8(out).unwrap().split("\r\n").map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            "a\t1.0.0\tcrates-io\tMIT OR Apache-2.0\tMIT\tcrate\tLICENSE\tCopyright (c) 2017 \"A\", B and C\thttps://example.com/a\tOK\t",
+            lines[1]
+        );
+        assert_eq!(COLUMNS.len(), lines[0].split('\t').count());
+    }
+}
@@ -0,0 +1,450 @@
+//! CycloneDX 1.5 software bills of materials, in JSON or XML
+//!
+//! The license texts found by the hound are attached as license evidence of
+//! each component.
+
+use std::io::{self, Write};
+use std::ptr;
+
+use serde_json;
+
+use super::html::escape;
+use super::sbom::{self, TOOL_NAME, TOOL_VERSION};
+use license::sha256_hex;
+use timestamp;
+use LicenseReport;
+
+#[derive(Serialize)]
+struct Tool {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+struct Tools {
+    components: Vec<Tool>,
+}
+
+#[derive(Serialize)]
+struct Metadata {
+    timestamp: String,
+    tools: Tools,
+}
+
+#[derive(Serialize)]
+struct Hash<'a> {
+    alg: &'static str,
+    content: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Text<'a> {
+    content_type: &'static str,
+    content: &'a str,
+}
+
+#[derive(Serialize)]
+struct License<'a> {
+    id: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<Text<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum LicenseChoice<'a> {
+    License(License<'a>),
+    Expression(String),
+}
+
+#[derive(Serialize)]
+struct Evidence<'a> {
+    licenses: Vec<LicenseChoice<'a>>,
+}
+
+#[derive(Serialize)]
+struct ExternalReference<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    url: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Component<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(rename = "bom-ref")]
+    bom_ref: String,
+    name: &'a str,
+    version: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    hashes: Vec<Hash<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    licenses: Vec<LicenseChoice<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    copyright: Option<&'a str>,
+    purl: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    external_references: Vec<ExternalReference<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    evidence: Option<Evidence<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Dependency {
+    #[serde(rename = "ref")]
+    reference: String,
+    depends_on: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Bom<'a> {
+    bom_format: &'static str,
+    spec_version: &'static str,
+    serial_number: String,
+    version: u32,
+    metadata: Metadata,
+    components: Vec<Component<'a>>,
+    dependencies: Vec<Dependency>,
+}
+
+/// A version 4 style UUID derived from `seed`
+fn uuid(seed: &str) -> String {
+    let hash = sha256_hex(seed);
+    format!(
+        "{}-{}-4{}-{:x}{}-{}",
+        &hash[0..8], &hash[8..12], &hash[13..16],
+        8 + u8::from_str_radix(&hash[16..17], 16).unwrap() % 4, &hash[17..20],
+        &hash[20..32]
+    )
+}
+
+fn component(report: &LicenseReport, bom_ref: String) -> Component {
+    let description = report.conclusion.as_ref().ok();
+
+    // An expression cannot be combined with other licenses, so the declared
+    // expression is given as the license and the chosen license with its
+    // text as evidence. Declared licenses that are not valid expressions are
+    // left out.
+    let licenses = match (sbom::declared_license(report), sbom::concluded_license(report)) {
+        (Some(declared), _) => vec![LicenseChoice::Expression(declared)],
+        (None, Some(concluded)) => vec![LicenseChoice::License(License { id: concluded, text: None })],
+        (None, None) => vec![],
+    };
+
+    let evidence = description.map(|x| Evidence {
+        licenses: vec![LicenseChoice::License(License {
+            id: x.chosen_license.spdx_id(),
+            text: Some(Text { content_type: "text/plain", content: &x.full_license_document }),
+        })],
+    });
+
+    Component {
+        kind: "library",
+        bom_ref,
+        name: &report.package_name,
+        version: &report.version,
+        hashes: report.checksum.iter().map(|x| Hash { alg: "SHA-256", content: x }).collect(),
+        licenses,
+        copyright: sbom::copyright_notice(report),
+        purl: sbom::purl(report),
+        external_references: description.and_then(|x| x.link.as_ref())
+            .filter(|x| x.starts_with("https://") || x.starts_with("http://"))
+            .map(|x| ExternalReference { kind: "website", url: x })
+            .into_iter()
+            .collect(),
+        evidence,
+    }
+}
+
+fn bom(reports: &[LicenseReport], timestamp: String) -> Bom {
+    let ids = reports.iter().map(sbom::id_of).collect::<Vec<_>>().join("\n");
+
+    // The same crate may come from several sources, but references must be
+    // unique
+    let bom_refs = sbom::unique_ids(reports, sbom::purl, "#");
+    let ref_of = |report: &LicenseReport| bom_refs[reports.iter().position(|x| ptr::eq(x, report)).unwrap()].clone();
+
+    let dependencies = reports.iter()
+        .map(|report| Dependency {
+            reference: ref_of(report),
+            depends_on: report.dependencies.iter()
+                .filter_map(|dependency| reports.iter().find(|x| sbom::id_of(x) == *dependency))
+                .map(&ref_of)
+                .collect(),
+        })
+        .collect();
+
+    Bom {
+        bom_format: "CycloneDX",
+        spec_version: "1.5",
+        serial_number: format!("urn:uuid:{}", uuid(&format!("{}\n{}", timestamp, ids))),
+        version: 1,
+        metadata: Metadata {
+            timestamp,
+            tools: Tools {
+                components: vec![Tool { kind: "application", name: TOOL_NAME, version: TOOL_VERSION }],
+            },
+        },
+        components: reports.iter().zip(bom_refs.iter().cloned()).map(|(x, bom_ref)| component(x, bom_ref)).collect(),
+        dependencies,
+    }
+}
+
+pub fn write_json<W: Write>(out: &mut W, reports: &[LicenseReport]) -> Result<(), serde_json::Error> {
+    serde_json::to_writer_pretty(out, &bom(reports, timestamp::now()))
+}
+
+fn write_licenses<W: Write>(out: &mut W, indent: &str, licenses: &[LicenseChoice]) -> io::Result<()> {
+    writeln!(out, "{}<licenses>", indent)?;
+    for license in licenses {
+        match *license {
+            LicenseChoice::Expression(ref expression) =>
+                writeln!(out, "{}  <expression>{}</expression>", indent, escape(expression))?,
+            LicenseChoice::License(ref license) => {
+                writeln!(out, "{}  <license>", indent)?;
+                writeln!(out, "{}    <id>{}</id>", indent, escape(license.id))?;
+                if let Some(ref text) = license.text {
+                    writeln!(
+                        out, "{}    <text content-type=\"{}\">{}</text>",
+                        indent, escape(text.content_type), escape(text.content)
+                    )?;
+                }
+                writeln!(out, "{}  </license>", indent)?;
+            },
+        }
+    }
+    writeln!(out, "{}</licenses>", indent)
+}
+
+/// Elements are written in the order required by the XML schema
+fn write_xml_bom<W: Write>(out: &mut W, bom: &Bom) -> io::Result<()> {
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        out, "<bom xmlns=\"http://cyclonedx.org/schema/bom/{}\" serialNumber=\"{}\" version=\"{}\">",
+        bom.spec_version, escape(&bom.serial_number), bom.version
+    )?;
+
+    writeln!(out, "  <metadata>")?;
+    writeln!(out, "    <timestamp>{}</timestamp>", escape(&bom.metadata.timestamp))?;
+    writeln!(out, "    <tools>")?;
+    writeln!(out, "      <components>")?;
+    for tool in &bom.metadata.tools.components {
+        writeln!(out, "        <component type=\"{}\">", tool.kind)?;
+        writeln!(out, "          <name>{}</name>", escape(tool.name))?;
+        writeln!(out, "          <version>{}</version>", escape(tool.version))?;
+        writeln!(out, "        </component>")?;
+    }
+    writeln!(out, "      </components>")?;
+    writeln!(out, "    </tools>")?;
+    writeln!(out, "  </metadata>")?;
+
+    writeln!(out, "  <components>")?;
+    for component in &bom.components {
+        writeln!(out, "    <component type=\"{}\" bom-ref=\"{}\">", component.kind, escape(&component.bom_ref))?;
+        writeln!(out, "      <name>{}</name>", escape(component.name))?;
+        writeln!(out, "      <version>{}</version>", escape(component.version))?;
+        if !component.hashes.is_empty() {
+            writeln!(out, "      <hashes>")?;
+            for hash in &component.hashes {
+                writeln!(out, "        <hash alg=\"{}\">{}</hash>", hash.alg, escape(hash.content))?;
+            }
+            writeln!(out, "      </hashes>")?;
+        }
+        if !component.licenses.is_empty() {
+            write_licenses(out, "      ", &component.licenses)?;
+        }
+        if let Some(copyright) = component.copyright {
+            writeln!(out, "      <copyright>{}</copyright>", escape(copyright))?;
+        }
+        writeln!(out, "      <purl>{}</purl>", escape(&component.purl))?;
+        if !component.external_references.is_empty() {
+            writeln!(out, "      <externalReferences>")?;
+            for reference in &component.external_references {
+                writeln!(out, "        <reference type=\"{}\">", reference.kind)?;
+                writeln!(out, "          <url>{}</url>", escape(reference.url))?;
+                writeln!(out, "        </reference>")?;
+            }
+            writeln!(out, "      </externalReferences>")?;
+        }
+        if let Some(ref evidence) = component.evidence {
+            writeln!(out, "      <evidence>")?;
+            write_licenses(out, "        ", &evidence.licenses)?;
+            writeln!(out, "      </evidence>")?;
+        }
+        writeln!(out, "    </component>")?;
+    }
+    writeln!(out, "  </components>")?;
+
+    writeln!(out, "  <dependencies>")?;
+    for dependency in &bom.dependencies {
+        if dependency.depends_on.is_empty() {
+            writeln!(out, "    <dependency ref=\"{}\"/>", escape(&dependency.reference))?;
+            continue;
+        }
+
+        writeln!(out, "    <dependency ref=\"{}\">", escape(&dependency.reference))?;
+        for reference in &dependency.depends_on {
+            writeln!(out, "      <dependency ref=\"{}\"/>", escape(reference))?;
+        }
+        writeln!(out, "    </dependency>")?;
+    }
+    writeln!(out, "  </dependencies>")?;
+
+    writeln!(out, "</bom>")
+}
+
+pub fn write_xml<W: Write>(out: &mut W, reports: &[LicenseReport]) -> io::Result<()> {
+    write_xml_bom(out, &bom(reports, timestamp::now()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use license::LicenseId;
+    use output::fixture;
+
+    fn reports() -> Vec<LicenseReport> {
+        let mut reports = vec![
+            fixture::ok("app", "0.1.0", LicenseId::Mit, "Copyright (c) 2017 App"),
+            fixture::ok("a", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 A & B"),
+            fixture::unrecoverable("b"),
+        ];
+        reports[0].dependencies = vec!["a 1.0.0".to_string(), "b 1.0.0".to_string()];
+        reports[1].checksum = Some("abc".to_string());
+        reports[1].conclusion.as_mut().unwrap().full_spdx_license = "MIT/Apache-2.0".to_string();
+        reports[1].conclusion.as_mut().unwrap().link = Some("https://example.com/a".to_string());
+        reports
+    }
+
+    #[test]
+    fn can_make_uuids() {
+        let uuid = uuid("seed");
+        assert_eq!(36, uuid.len());
+        assert_eq!("4", &uuid[14..15]);
+        assert!("89ab".contains(&uuid[19..20]));
+    }
+
+    #[test]
+    fn can_write_json() {
+        let reports = reports();
+        let bom = serde_json::to_value(&bom(&reports, "2017-11-04T13:37:00Z".to_string())).unwrap();
+
+        assert_eq!("CycloneDX", bom["bomFormat"]);
+        assert_eq!("1.5", bom["specVersion"]);
+        assert!(bom["serialNumber"].as_str().unwrap().starts_with("urn:uuid:"));
+        assert_eq!("2017-11-04T13:37:00Z", bom["metadata"]["timestamp"]);
+
+        assert_eq!(
+            json!({
+                "type": "library",
+                "bom-ref": "pkg:cargo/a@1.0.0",
+                "name": "a",
+                "version": "1.0.0",
+                "hashes": [{ "alg": "SHA-256", "content": "abc" }],
+                "licenses": [{ "expression": "MIT OR Apache-2.0" }],
+                "copyright": "Copyright (c) 2017 A & B",
+                "purl": "pkg:cargo/a@1.0.0",
+                "externalReferences": [{ "type": "website", "url": "https://example.com/a" }],
+                "evidence": {
+                    "licenses": [{
+                        "license": {
+                            "id": "MIT",
+                            "text": { "contentType": "text/plain", "content": "Copyright (c) 2017 A & B" },
+                        },
+                    }],
+                },
+            }),
+            bom["components"][1]
+        );
+
+        assert_eq!(
+            json!({ "type": "library", "bom-ref": "pkg:cargo/b@1.0.0", "name": "b", "version": "1.0.0", "purl": "pkg:cargo/b@1.0.0" }),
+            bom["components"][2]
+        );
+
+        assert_eq!(
+            json!({ "ref": "pkg:cargo/app@0.1.0", "dependsOn": ["pkg:cargo/a@1.0.0", "pkg:cargo/b@1.0.0"] }),
+            bom["dependencies"][0]
+        );
+    }
+
+    #[test]
+    fn gives_every_component_its_own_ref() {
+        let mut reports = reports();
+        reports.push(fixture::ok("a", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 A"));
+        reports[3].source = Some("registry+https://git.example.com/index".to_string());
+        reports[3].conclusion.as_mut().unwrap().full_spdx_license = "MIT or Apache 2".to_string();
+
+        let bom = serde_json::to_value(&bom(&reports, "2017-11-04T13:37:00Z".to_string())).unwrap();
+
+        let refs = bom["components"].as_array().unwrap().iter()
+            .map(|x| x["bom-ref"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!("pkg:cargo/app@0.1.0", refs[0]);
+        assert!(refs[1].starts_with("pkg:cargo/a@1.0.0#"));
+        assert!(refs[3].starts_with("pkg:cargo/a@1.0.0#"));
+        assert_ne!(refs[1], refs[3]);
+
+        assert_eq!(refs[1], bom["dependencies"][0]["dependsOn"][0]);
+        assert_eq!(refs[3], bom["dependencies"][3]["ref"]);
+
+        // Not a valid expression, so only the chosen license is given
+        assert_eq!(json!([{ "license": { "id": "MIT" } }]), bom["components"][3]["licenses"]);
+    }
+
+    #[test]
+    fn can_write_xml() {
+        let reports = reports();
+        let mut out = vec![];
+        write_xml_bom(&mut out, &bom(&reports, "2017-11-04T13:37:00Z".to_string())).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<bom xmlns=\"http://cyclonedx.org/schema/bom/1.5\" serialNumber=\"urn:uuid:"));
+
+        let component = [
+            "    <component type=\"library\" bom-ref=\"pkg:cargo/a@1.0.0\">",
+            "      <name>a</name>",
+            "      <version>1.0.0</version>",
+            "      <hashes>",
+            "        <hash alg=\"SHA-256\">abc</hash>",
+            "      </hashes>",
+            "      <licenses>",
+            "        <expression>MIT OR Apache-2.0</expression>",
+            "      </licenses>",
+            "      <copyright>Copyright (c) 2017 A &amp; B</copyright>",
+            "      <purl>pkg:cargo/a@1.0.0</purl>",
+            "      <externalReferences>",
+            "        <reference type=\"website\">",
+            "          <url>https://example.com/a</url>",
+            "        </reference>",
+            "      </externalReferences>",
+            "      <evidence>",
+            "        <licenses>",
+            "          <license>",
+            "            <id>MIT</id>",
+            "            <text content-type=\"text/plain\">Copyright (c) 2017 A &amp; B</text>",
+            "          </license>",
+            "        </licenses>",
+            "      </evidence>",
+            "    </component>",
+        ].join("\n");
+        assert!(out.contains(&component));
+
+        let dependencies = [
+            "    <dependency ref=\"pkg:cargo/app@0.1.0\">",
+            "      <dependency ref=\"pkg:cargo/a@1.0.0\"/>",
+            "      <dependency ref=\"pkg:cargo/b@1.0.0\"/>",
+            "    </dependency>",
+            "    <dependency ref=\"pkg:cargo/a@1.0.0\"/>",
+        ].join("\n");
+        assert!(out.contains(&dependencies));
+        assert!(out.ends_with("  </dependencies>\n</bom>\n"));
+    }
+}
@@ -0,0 +1,160 @@
+//! Machine-readable `debian/copyright` files, as specified by
+//! [DEP-5](https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/),
+//! for crates vendored with `cargo vendor`
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+use super::attribution::{self, Attribution};
+use super::sbom::TOOL_NAME;
+use license::LicenseId;
+use LicenseReport;
+
+const VENDOR_DIR: &str = "vendor";
+
+/// The short names used by Debian, which differ from the SPDX identifiers
+fn short_name(license: LicenseId) -> &'static str {
+    match license {
+        LicenseId::Mit => "Expat",
+        LicenseId::Apache2 => "Apache-2.0",
+        LicenseId::Bsd2Clause => "BSD-2-clause",
+        LicenseId::Bsd3Clause => "BSD-3-clause",
+        LicenseId::Isc => "ISC",
+        LicenseId::Mpl2 => "MPL-2.0",
+        LicenseId::Zlib => "Zlib",
+    }
+}
+
+/// Formats a value spanning several lines, where continuation lines are
+/// indented and empty lines are given as ` .`
+fn multiline(value: &str) -> String {
+    value.lines()
+        .map(|x| if x.trim().is_empty() { " .".to_string() } else { format!(" {}", x) })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `cargo vendor` names the directory of a crate after the crate, adding the
+/// version only when several versions are vendored
+fn vendored_dir(x: &Attribution, versions: &HashMap<&str, usize>) -> String {
+    if versions.get(x.name).cloned().unwrap_or(0) > 1 {
+        format!("{}/{}-{}", VENDOR_DIR, x.name, x.version)
+    } else {
+        format!("{}/{}", VENDOR_DIR, x.name)
+    }
+}
+
+/// `upstream_name` names the software being packaged
+pub fn write<W: Write>(out: &mut W, reports: &[LicenseReport], upstream_name: Option<&str>) -> io::Result<()> {
+    let groups = attribution::group(reports);
+
+    // Crates with a license text of their own get a license paragraph of
+    // their own, named after the first crate that uses it
+    let mut licenses = vec![];
+    let mut named = HashSet::new();
+    for group in &groups {
+        let base_name = short_name(group.license);
+        if named.insert(base_name) {
+            licenses.push(base_name.to_string());
+        } else {
+            licenses.push(format!("{}~{}", base_name, group.crates[0].name));
+        }
+    }
+
+    let mut crates = groups.iter().zip(&licenses)
+        .flat_map(|(group, license)| group.crates.iter().map(move |x| (x, license)))
+        .collect::<Vec<_>>();
+    crates.sort_by(|a, b| (a.0.name, a.0.version).cmp(&(b.0.name, b.0.version)));
+
+    let mut versions = HashMap::new();
+    for &(x, _) in &crates {
+        *versions.entry(x.name).or_insert(0) += 1;
+    }
+
+    writeln!(out, "Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/")?;
+    if let Some(upstream_name) = upstream_name {
+        writeln!(out, "Upstream-Name: {}", upstream_name)?;
+    }
+    writeln!(out, "Comment: Vendored crates, as found by {}", TOOL_NAME)?;
+
+    for (x, license) in crates {
+        writeln!(out)?;
+        writeln!(out, "Files: {}/*", vendored_dir(x, &versions))?;
+        writeln!(out, "Copyright:{}", multiline(x.copyright_notice))?;
+        writeln!(out, "License: {}", license)?;
+        if let Some(link) = x.link {
+            writeln!(out, "Comment: {} {}, {}", x.name, x.version, link)?;
+        }
+    }
+
+    for (group, license) in groups.iter().zip(&licenses) {
+        writeln!(out)?;
+        writeln!(out, "License: {}", license)?;
+        writeln!(out, "{}", multiline(&group.license_text))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use output::fixture;
+
+    #[test]
+    fn can_write_copyright_file() {
+        let mit_a = "Copyright (c) 2017 A\n\nPermission is hereby granted";
+        let mit_b = "Copyright (c) 2017 B\n\nPermission is hereby granted";
+
+        let mut reports = vec![
+            fixture::ok("a", "1.0.0", LicenseId::Mit, mit_a),
+            fixture::ok("a", "0.9.0", LicenseId::Mit, mit_a),
+            fixture::ok("b", "0.1.0", LicenseId::Mit, mit_b),
+            fixture::ok("c", "2.0.0", LicenseId::Bsd3Clause, "Copyright (c) 2017 C"),
+            fixture::unrecoverable("d"),
+        ];
+        reports[2].conclusion.as_mut().unwrap().link = Some("https://example.com/b".to_string());
+
+        let mut out = vec![];
+        write(&mut out, &reports, Some("our-product")).unwrap();
+
+        let expected = [
+            "Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/",
+            "Upstream-Name: our-product",
+            &format!("Comment: Vendored crates, as found by {}", TOOL_NAME),
+            "",
+            "Files: vendor/a-0.9.0/*",
+            "Copyright: Copyright (c) 2017 A",
+            "License: Expat",
+            "",
+            "Files: vendor/a-1.0.0/*",
+            "Copyright: Copyright (c) 2017 A",
+            "License: Expat",
+            "",
+            "Files: vendor/b/*",
+            "Copyright: Copyright (c) 2017 B",
+            "License: Expat~b",
+            "Comment: b 0.1.0, https://example.com/b",
+            "",
+            "Files: vendor/c/*",
+            "Copyright: Copyright (c) 2017 C",
+            "License: BSD-3-clause",
+            "",
+            "License: BSD-3-clause",
+            " Copyright (c) 2017 C",
+            "",
+            "License: Expat",
+            " Copyright (c) 2017 A",
+            " .",
+            " Permission is hereby granted",
+            "",
+            "License: Expat~b",
+            " Copyright (c) 2017 B",
+            " .",
+            " Permission is hereby granted",
+            "",
+        ];
+
+        assert_eq!(expected.join("\n"), String::from_utf8(out).unwrap());
+    }
+}
@@ -0,0 +1,147 @@
+//! A self-contained HTML page crediting all crates with a recovered license,
+//! for "Open source licenses" pages in applications
+
+use std::io::{self, Write};
+
+use super::attribution::{self, Attribution, Group, INTRODUCTION};
+use LicenseReport;
+
+const DEFAULT_STYLESHEET: &str = "\
+body { font-family: sans-serif; max-width: 50em; margin: 0 auto; padding: 1em; }
+pre { white-space: pre-wrap; background: #f4f4f4; padding: 1em; }
+section { margin-top: 2em; }
+.crates { list-style: none; padding: 0; }
+.crates li { margin-bottom: 0.5em; }
+.copyright { display: block; color: #555; }
+";
+
+/// Escapes `text` for HTML and XML
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn crate_anchor(x: &Attribution) -> String {
+    escape(&format!("crate-{}-{}", x.name, x.version))
+}
+
+/// Links come from the crate manifests, so only web links are made
+/// clickable
+fn is_web_link(link: &str) -> bool {
+    link.starts_with("https://") || link.starts_with("http://")
+}
+
+/// Writes the page with `stylesheet` embedded, or a simple default style
+pub fn write<W: Write>(out: &mut W, reports: &[LicenseReport], title: &str, stylesheet: Option<&str>) -> io::Result<()> {
+    let groups = attribution::group(reports);
+
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>{}</title>", escape(title))?;
+    writeln!(out, "<style>")?;
+    write!(out, "{}", stylesheet.unwrap_or(DEFAULT_STYLESHEET))?;
+    writeln!(out, "</style>")?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    writeln!(out, "<h1>{}</h1>", escape(title))?;
+    writeln!(out, "<p>{}</p>", escape(INTRODUCTION))?;
+
+    writeln!(out, "<nav>")?;
+    writeln!(out, "<ul class=\"toc\">")?;
+    for (i, group) in groups.iter().enumerate() {
+        writeln!(out, "<li><a href=\"#license-{}\">{}</a>", i + 1, escape(group.license.spdx_id()))?;
+        writeln!(out, "<ul>")?;
+        for x in &group.crates {
+            writeln!(out, "<li><a href=\"#{}\">{} {}</a></li>", crate_anchor(x), escape(x.name), escape(x.version))?;
+        }
+        writeln!(out, "</ul>")?;
+        writeln!(out, "</li>")?;
+    }
+    writeln!(out, "</ul>")?;
+    writeln!(out, "</nav>")?;
+
+    for (i, &Group { license, ref license_text, ref crates }) in groups.iter().enumerate() {
+        writeln!(out, "<section id=\"license-{}\">", i + 1)?;
+        writeln!(out, "<h2>{}</h2>", escape(license.spdx_id()))?;
+        writeln!(out, "<ul class=\"crates\">")?;
+        for x in crates {
+            write!(out, "<li id=\"{}\">", crate_anchor(x))?;
+            match x.link {
+                Some(link) if is_web_link(link) =>
+                    write!(out, "<a href=\"{}\">{}</a>", escape(link), escape(x.name))?,
+                _ => write!(out, "{}", escape(x.name))?,
+            }
+            write!(out, " {}", escape(x.version))?;
+            writeln!(out, "<span class=\"copyright\">{}</span></li>", escape(x.copyright_notice))?;
+        }
+        writeln!(out, "</ul>")?;
+        writeln!(out, "<pre>{}</pre>", escape(license_text))?;
+        writeln!(out, "</section>")?;
+    }
+
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use license::LicenseId;
+    use output::fixture;
+
+    fn page(reports: &[LicenseReport], stylesheet: Option<&str>) -> String {
+        let mut out = vec![];
+        write(&mut out, reports, "Open <source> licenses", stylesheet).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn can_write_page() {
+        let mut reports = vec![
+            fixture::ok("a", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 A <a@example.com>"),
+            fixture::ok("b", "0.1.0", LicenseId::Bsd3Clause, "Copyright (c) 2017 B & C"),
+        ];
+        reports[0].conclusion.as_mut().unwrap().link = Some("https://example.com/a?x=1&y=2".to_string());
+        reports[1].conclusion.as_mut().unwrap().link = Some("javascript:alert(1)".to_string());
+
+        let page = page(&reports, None);
+
+        assert!(page.contains("<title>Open &lt;source&gt; licenses</title>"));
+        assert!(page.contains(DEFAULT_STYLESHEET));
+
+        assert!(page.contains("<li><a href=\"#license-1\">BSD-3-Clause</a>"));
+        assert!(page.contains("<li><a href=\"#license-2\">MIT</a>"));
+        assert!(page.contains("<li><a href=\"#crate-a-1.0.0\">a 1.0.0</a></li>"));
+
+        assert!(page.contains(
+            "<li id=\"crate-a-1.0.0\"><a href=\"https://example.com/a?x=1&amp;y=2\">a</a> 1.0.0\
+            <span class=\"copyright\">Copyright (c) 2017 A &lt;a@example.com&gt;</span></li>"
+        ));
+        assert!(page.contains("<li id=\"crate-b-0.1.0\">b 0.1.0"));
+        assert!(!page.contains("javascript:"));
+
+        assert!(page.contains("<pre>Copyright (c) 2017 B &amp; C</pre>"));
+    }
+
+    #[test]
+    fn can_use_custom_stylesheet() {
+        let page = page(&[], Some("body { color: red; }\n"));
+
+        assert!(page.contains("<style>\nbody { color: red; }\n</style>"));
+        assert!(!page.contains(DEFAULT_STYLESHEET));
+    }
+}
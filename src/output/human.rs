@@ -0,0 +1,173 @@
+//! A table of the reports for reading in the terminal, followed by a summary
+
+use std::collections::BTreeMap;
+use std::io;
+
+use termcolor::{Color, ColorSpec, WriteColor};
+
+use super::Status;
+use LicenseReport;
+
+fn write_status<W: WriteColor>(out: &mut W, status: &Status) -> io::Result<()> {
+    let (color, detail) = match *status {
+        Status::Ok => (Color::Green, None),
+        Status::Warn(ref detail) => (Color::Yellow, Some(detail)),
+        Status::Error(ref detail) => (Color::Red, Some(detail)),
+    };
+
+    out.set_color(ColorSpec::new().set_fg(Some(color)).set_bold(true))?;
+    write!(out, "{}", status.label())?;
+    out.reset()?;
+
+    if let Some(detail) = detail {
+        write!(out, " {}", detail)?;
+    }
+
+    Ok(())
+}
+
+struct Row<'a> {
+    name: &'a str,
+    version: &'a str,
+    license: &'a str,
+    source: &'a str,
+    status: Status,
+}
+
+fn column_width<'a, I: Iterator<Item=&'a str>>(heading: &str, values: I) -> usize {
+    values.map(|x| x.len()).chain(Some(heading.len())).max().unwrap()
+}
+
+fn counts<'a, I: Iterator<Item=&'a str>>(items: I) -> Vec<(&'a str, usize)> {
+    let mut counts = BTreeMap::new();
+    for item in items {
+        *counts.entry(item).or_insert(0) += 1;
+    }
+
+    // Most common first, alphabetically among equals
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_by(|a, b| b.1.cmp(&a.1));
+    counts
+}
+
+fn write_counts<W: WriteColor>(out: &mut W, heading: &str, counts: &[(&str, usize)]) -> io::Result<()> {
+    if counts.is_empty() {
+        return Ok(());
+    }
+
+    let width = counts.iter().map(|x| x.0.len()).max().unwrap_or(0);
+
+    writeln!(out)?;
+    writeln!(out, "{}:", heading)?;
+    for &(name, count) in counts {
+        writeln!(out, "    {:width$}  {:>4}", name, count, width = width)?;
+    }
+
+    Ok(())
+}
+
+pub fn write<W: WriteColor>(out: &mut W, reports: &[LicenseReport]) -> io::Result<()> {
+    let rows = reports.iter()
+        .map(|report| {
+            let description = report.conclusion.as_ref().ok();
+            Row {
+                name: &report.package_name,
+                version: &report.version,
+                license: description.map(|x| x.chosen_license.spdx_id()).unwrap_or("-"),
+                source: description.map(|x| x.license_source.origin.kind()).unwrap_or("-"),
+                status: Status::of(report),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let headings = ("CRATE", "VERSION", "LICENSE", "SOURCE");
+    let name_width = column_width(headings.0, rows.iter().map(|x| x.name));
+    let version_width = column_width(headings.1, rows.iter().map(|x| x.version));
+    let license_width = column_width(headings.2, rows.iter().map(|x| x.license));
+    let source_width = column_width(headings.3, rows.iter().map(|x| x.source));
+
+    out.set_color(ColorSpec::new().set_bold(true))?;
+    write!(
+        out, "{:4$}  {:5$}  {:6$}  {:7$}  STATUS",
+        headings.0, headings.1, headings.2, headings.3,
+        name_width, version_width, license_width, source_width,
+    )?;
+    out.reset()?;
+    writeln!(out)?;
+
+    for row in &rows {
+        write!(
+            out, "{:4$}  {:5$}  {:6$}  {:7$}  ",
+            row.name, row.version, row.license, row.source,
+            name_width, version_width, license_width, source_width,
+        )?;
+        write_status(out, &row.status)?;
+        writeln!(out)?;
+    }
+
+    let licenses = counts(reports.iter().filter_map(|x| x.conclusion.as_ref().ok()).map(|x| x.chosen_license.spdx_id()));
+    let errors = counts(reports.iter().filter_map(|x| x.conclusion.as_ref().err()).map(|x| x.kind()));
+    let warnings = counts(reports.iter().flat_map(|x| x.findings.iter()).map(|x| x.kind()));
+
+    write_counts(out, "Licenses", &licenses)?;
+    write_counts(out, "Warnings", &warnings)?;
+    write_counts(out, "Errors", &errors)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use termcolor::NoColor;
+
+    use license::{Finding, LicenseId};
+    use output::fixture;
+
+    #[test]
+    fn can_write_table_and_summary() {
+        let mismatch = Finding::LicenseMismatch {
+            service: "GitHub license API".to_string(),
+            url: "https://api.github.com/repos/owner/b/license".to_string(),
+            detected_license: "Apache-2.0".to_string(),
+            chosen_license: LicenseId::Mit,
+            license_text: "".to_string(),
+        };
+
+        let text = "Copyright (c) 2017 Someone";
+        let mut reports = vec![
+            fixture::ok("a", "1.0.0", LicenseId::Mit, text),
+            fixture::ok("bb", "1.0.0", LicenseId::Mit, text),
+            fixture::ok("c", "1.0.0", LicenseId::Bsd3Clause, text),
+            fixture::unrecoverable("d"),
+        ];
+        reports[1].findings.push(mismatch);
+
+        let mut out = NoColor::new(vec![]);
+        write(&mut out, &reports).unwrap();
+
+        let expected = [
+            "CRATE  VERSION  LICENSE       SOURCE  STATUS",
+            "a      1.0.0    MIT           crate   OK",
+            "bb     1.0.0    MIT           crate   WARN LicenseMismatch",
+            "c      1.0.0    BSD-3-Clause  crate   OK",
+            "d      1.0.0    -             -       ERROR UnableToRecoverLicenseFile",
+            "",
+            "Licenses:",
+            "    MIT              2",
+            "    BSD-3-Clause     1",
+            "",
+            "Warnings:",
+            "    LicenseMismatch     1",
+            "",
+            "Errors:",
+            "    UnableToRecoverLicenseFile     1",
+            "",
+        ];
+
+        assert_eq!(
+            expected.join("\n"),
+            String::from_utf8(out.into_inner()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,343 @@
+//! The different ways of presenting the license reports
+
+mod attribution;
+mod csv;
+mod cyclonedx;
+mod dep5;
+pub mod html;
+mod human;
+mod notices;
+pub mod sbom;
+mod spdx;
+mod template;
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use atty;
+use handlebars::{self, TemplateRenderError};
+use serde_json;
+use termcolor::{ColorChoice, StandardStream};
+
+use report::{Document, Report};
+use LicenseReport;
+use read_file;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Serializing(serde_json::Error),
+    Template(TemplateRenderError),
+
+    /// Template output was asked for without a template
+    MissingTemplate,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Error::Io(ref err) => write!(f, "{}", err),
+            &Error::Serializing(ref err) => write!(f, "{}", err),
+            &Error::Template(ref err) => write!(f, "{}", err),
+            &Error::MissingTemplate => write!(f, "Template output requires a template"),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(other: io::Error) -> Error {
+        Error::Io(other)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(other: serde_json::Error) -> Error {
+        Error::Serializing(other)
+    }
+}
+
+impl From<TemplateRenderError> for Error {
+    fn from(other: TemplateRenderError) -> Error {
+        Error::Template(other)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// A table for reading in the terminal
+    Human,
+
+    /// The full report, for further processing, as described by
+    /// `schema/report-v1.schema.json`
+    Json,
+
+    /// A THIRD-PARTY-NOTICES document in plain text
+    Notices,
+
+    /// A THIRD-PARTY-NOTICES document in Markdown
+    NoticesMarkdown,
+
+    /// A self-contained HTML page with the same contents as the notices
+    Html,
+
+    /// Rendered from a user-supplied template
+    Template,
+
+    /// An SPDX 2.3 document in JSON
+    SpdxJson,
+
+    /// An SPDX 2.3 document in tag-value form
+    SpdxTagValue,
+
+    /// A CycloneDX 1.5 bill of materials in JSON
+    CycloneDxJson,
+
+    /// A CycloneDX 1.5 bill of materials in XML
+    CycloneDxXml,
+
+    /// A machine-readable `debian/copyright` file
+    Dep5,
+
+    /// One row per crate, for spreadsheets
+    Csv,
+
+    /// Like `Csv`, but separated by tabs
+    Tsv,
+}
+
+const FORMATS: &[(&str, Format)] = &[
+    ("human", Format::Human),
+    ("json", Format::Json),
+    ("notices", Format::Notices),
+    ("notices-markdown", Format::NoticesMarkdown),
+    ("html", Format::Html),
+    ("template", Format::Template),
+    ("spdx-json", Format::SpdxJson),
+    ("spdx-tag-value", Format::SpdxTagValue),
+    ("cyclonedx-json", Format::CycloneDxJson),
+    ("cyclonedx-xml", Format::CycloneDxXml),
+    ("dep5", Format::Dep5),
+    ("csv", Format::Csv),
+    ("tsv", Format::Tsv),
+];
+
+impl Format {
+    /// Attribution documents only include crates with a recovered license
+    fn is_attribution(&self) -> bool {
+        match *self {
+            Format::Human | Format::Json | Format::Csv | Format::Tsv => false,
+            Format::SpdxJson | Format::SpdxTagValue | Format::CycloneDxJson | Format::CycloneDxXml => false,
+            Format::Notices | Format::NoticesMarkdown | Format::Html | Format::Template | Format::Dep5 => true,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = UnknownFormat;
+
+    fn from_str(s: &str) -> Result<Format, UnknownFormat> {
+        FORMATS.iter()
+            .find(|&&(name, _)| name == s)
+            .map(|&(_, format)| format)
+            .ok_or_else(|| UnknownFormat(s.to_string()))
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownFormat(String);
+
+impl fmt::Display for UnknownFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown output format {:?}. Expected one of:", self.0)?;
+        for &(name, _) in FORMATS {
+            write!(f, " {}", name)?;
+        }
+        Ok(())
+    }
+}
+
+/// How a crate fared, with the kinds of findings or the kind of error
+pub enum Status {
+    Ok,
+    Warn(String),
+    Error(String),
+}
+
+impl Status {
+    pub fn of(report: &LicenseReport) -> Status {
+        match report.conclusion {
+            Ok(_) if report.findings.is_empty() => Status::Ok,
+            Ok(_) => Status::Warn(
+                report.findings.iter().map(|x| x.kind()).collect::<Vec<_>>().join(", ")
+            ),
+            Err(ref err) => Status::Error(err.kind().to_string()),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match *self {
+            Status::Ok => "OK",
+            Status::Warn(_) => "WARN",
+            Status::Error(_) => "ERROR",
+        }
+    }
+}
+
+/// Settings for the attribution documents
+#[derive(Debug, Default)]
+pub struct Options {
+    /// Replaces the default title
+    pub title: Option<String>,
+
+    /// CSS to embed in HTML output instead of the default style
+    pub stylesheet: Option<String>,
+
+    /// The Handlebars template for template output
+    pub template: Option<Template>,
+}
+
+/// A Handlebars template, as given by `--template`
+#[derive(Debug)]
+pub struct Template {
+    /// Values are HTML escaped for templates named like `*.html`
+    pub path: PathBuf,
+    pub text: String,
+}
+
+impl Template {
+    /// Reads the template at `path`, checking its syntax
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Template, Error> {
+        let text = read_file(&path)?;
+        handlebars::Template::compile(&text).map_err(TemplateRenderError::from)?;
+
+        Ok(Template { path: path.as_ref().to_owned(), text })
+    }
+}
+
+/// Writes `report` to stdout in the given format. Colors are only used
+/// when stdout is a terminal.
+pub fn write(format: Format, report: &Report, options: &Options) -> Result<(), Error> {
+    let title = options.title.as_ref().map(|x| &x[..]).unwrap_or(attribution::DEFAULT_TITLE);
+    let reports = &report.crates[..];
+
+    match format {
+        Format::Human => {
+            let color_choice = if atty::is(atty::Stream::Stdout) { ColorChoice::Auto } else { ColorChoice::Never };
+            human::write(&mut StandardStream::stdout(color_choice), reports)?;
+        },
+        Format::Json => serde_json::to_writer(io::stdout(), &Document::from(report))?,
+        Format::Notices => notices::write_text(&mut io::stdout(), reports, title)?,
+        Format::NoticesMarkdown => notices::write_markdown(&mut io::stdout(), reports, title)?,
+        Format::Html => {
+            html::write(&mut io::stdout(), reports, title, options.stylesheet.as_ref().map(|x| &x[..]))?;
+        },
+        Format::Template => {
+            let template = options.template.as_ref().ok_or(Error::MissingTemplate)?;
+            template::write(&mut io::stdout(), reports, title, &template.path, &template.text)?;
+        },
+        Format::SpdxJson => spdx::write_json(&mut io::stdout(), reports, options.title.as_ref().map(|x| &x[..]))?,
+        Format::SpdxTagValue => spdx::write_tag_value(&mut io::stdout(), reports, options.title.as_ref().map(|x| &x[..]))?,
+        Format::CycloneDxJson => cyclonedx::write_json(&mut io::stdout(), reports)?,
+        Format::CycloneDxXml => cyclonedx::write_xml(&mut io::stdout(), reports)?,
+        Format::Csv => csv::write_csv(&mut io::stdout(), reports)?,
+        Format::Tsv => csv::write_tsv(&mut io::stdout(), reports)?,
+        Format::Dep5 => dep5::write(&mut io::stdout(), reports, options.title.as_ref().map(|x| &x[..]))?,
+    }
+
+    if format.is_attribution() {
+        let missing = attribution::missing(reports).len();
+        if missing > 0 {
+            eprintln!(
+                "WARN {} crates are missing from the attribution document because their licenses could not be recovered",
+                missing
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Reports for testing the output formats
+#[cfg(test)]
+pub mod fixture {
+    use std::path::PathBuf;
+
+    use license::{LicenseId, LicenseOrigin, LicenseSource};
+    use {LicenseDescription, LicenseError, LicenseReport, recover_copyright_notice};
+
+    const CRATES_IO: &str = "registry+https://github.com/rust-lang/crates.io-index";
+
+    /// A crate with `license_text` recovered from the crate itself
+    pub fn ok(name: &str, version: &str, license: LicenseId, license_text: &str) -> LicenseReport {
+        LicenseReport {
+            package_name: name.to_string(),
+            version: version.to_string(),
+            registry: Some("crates-io".to_string()),
+            source: Some(CRATES_IO.to_string()),
+            checksum: None,
+            dependencies: vec![],
+            findings: vec![],
+            conclusion: Ok(LicenseDescription {
+                chosen_license: license,
+                copyright_notice: recover_copyright_notice(license_text).unwrap(),
+                full_spdx_license: license.spdx_id().to_string(),
+                full_license_document: license_text.to_string(),
+                license_source: LicenseSource::new(LicenseOrigin::Crate("LICENSE".to_string()), license_text),
+                link: None,
+            }),
+        }
+    }
+
+    /// A crate without a license file
+    pub fn unrecoverable(name: &str) -> LicenseReport {
+        LicenseReport {
+            package_name: name.to_string(),
+            version: "1.0.0".to_string(),
+            registry: Some("crates-io".to_string()),
+            source: Some(CRATES_IO.to_string()),
+            checksum: None,
+            dependencies: vec![],
+            findings: vec![],
+            conclusion: Err(LicenseError::UnableToRecoverLicenseFile(PathBuf::from(format!("{}/Cargo.toml", name)))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn can_parse_formats() {
+        assert_eq!(Format::Human, "human".parse().unwrap());
+        assert_eq!(Format::Json, "json".parse().unwrap());
+
+        let err = "yaml".parse::<Format>().unwrap_err();
+        assert_eq!("Unknown output format \"yaml\". Expected one of: human json notices notices-markdown html template spdx-json spdx-tag-value cyclonedx-json cyclonedx-xml dep5 csv tsv", err.to_string());
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        use std::fs;
+        use tempdir::TempDir;
+
+        let dir = TempDir::new("cargo-license-hound-template").unwrap();
+
+        let path = dir.path().join("notices.txt.hbs");
+        fs::write(&path, "{{#each groups}}{{license}}").unwrap();
+        match Template::from_file(&path) {
+            Err(Error::Template(_)) => {},
+            x => panic!("Unexpected result {:?}", x),
+        }
+
+        match Template::from_file(dir.path().join("missing.hbs")) {
+            Err(Error::Io(_)) => {},
+            x => panic!("Unexpected result {:?}", x),
+        }
+
+        fs::write(&path, "{{#each groups}}{{license}}{{/each}}").unwrap();
+        assert_eq!(path, Template::from_file(&path).unwrap().path);
+    }
+}
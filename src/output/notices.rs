@@ -0,0 +1,187 @@
+//! THIRD-PARTY-NOTICES documents crediting all crates with a recovered
+//! license, in plain text or Markdown
+
+use std::io::{self, Write};
+
+use super::attribution::{self, Group, INTRODUCTION};
+use LicenseReport;
+
+fn rule(c: char) -> String {
+    ::std::iter::repeat(c).take(80).collect()
+}
+
+pub fn write_text<W: Write>(out: &mut W, reports: &[LicenseReport], title: &str) -> io::Result<()> {
+    writeln!(out, "{}", title.to_uppercase())?;
+    writeln!(out)?;
+    writeln!(out, "{}", INTRODUCTION)?;
+
+    for Group { license, license_text, crates } in attribution::group(reports) {
+        writeln!(out)?;
+        writeln!(out, "{}", rule('='))?;
+        writeln!(out, "{}", license.spdx_id())?;
+        writeln!(out)?;
+
+        for x in crates {
+            writeln!(out, "  {} {}", x.name, x.version)?;
+            if let Some(link) = x.link {
+                writeln!(out, "    {}", link)?;
+            }
+            writeln!(out, "    {}", x.copyright_notice)?;
+        }
+
+        writeln!(out)?;
+        writeln!(out, "{}", rule('-'))?;
+        writeln!(out, "{}", license_text)?;
+    }
+
+    Ok(())
+}
+
+/// A code fence longer than any run of backticks in `text`, so the text
+/// cannot end the code block early
+fn fence_for(text: &str) -> String {
+    let longest_run = text.split(|c| c != '`').map(|x| x.len()).max().unwrap_or(0);
+    "`".repeat(::std::cmp::max(3, longest_run + 1))
+}
+
+/// Escapes the characters that have a meaning in Markdown inline text
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\`*_[]<>#|".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+pub fn write_markdown<W: Write>(out: &mut W, reports: &[LicenseReport], title: &str) -> io::Result<()> {
+    writeln!(out, "# {}", escape_markdown(title))?;
+    writeln!(out)?;
+    writeln!(out, "{}", INTRODUCTION)?;
+
+    for Group { license, license_text, crates } in attribution::group(reports) {
+        writeln!(out)?;
+        writeln!(out, "## {}", license.spdx_id())?;
+        writeln!(out)?;
+
+        for x in crates {
+            match x.link {
+                Some(link) => writeln!(out, " * [{} {}](<{}>)  ", escape_markdown(x.name), x.version, link)?,
+                None => writeln!(out, " * {} {}  ", escape_markdown(x.name), x.version)?,
+            }
+            writeln!(out, "   {}", escape_markdown(x.copyright_notice))?;
+        }
+
+        let fence = fence_for(&license_text);
+        writeln!(out)?;
+        writeln!(out, "{}", fence)?;
+        writeln!(out, "{}", license_text)?;
+        writeln!(out, "{}", fence)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use output::fixture;
+
+    fn reports() -> Vec<LicenseReport> {
+        let mit_a = "Copyright (c) 2017 A\r\n\r\nPermission is hereby granted  \r\n";
+        let mit_b = "Copyright (c) 2017 B\n\nPermission is hereby granted\n";
+
+        let mut reports = vec![
+            fixture::ok("b", "0.2.0", ::license::LicenseId::Mit, mit_b),
+            fixture::ok("a", "1.0.0", ::license::LicenseId::Mit, mit_a),
+            fixture::ok("a", "0.9.0", ::license::LicenseId::Mit, mit_a),
+            fixture::unrecoverable("c"),
+        ];
+        reports[1].conclusion.as_mut().unwrap().link = Some("https://example.com/a".to_string());
+        reports
+    }
+
+    #[test]
+    fn can_write_text() {
+        let mut out = vec![];
+        write_text(&mut out, &reports(), attribution::DEFAULT_TITLE).unwrap();
+
+        let expected = [
+            "THIRD-PARTY SOFTWARE NOTICES",
+            "",
+            INTRODUCTION,
+            "",
+            &rule('='),
+            "MIT",
+            "",
+            "  a 0.9.0",
+            "    Copyright (c) 2017 A",
+            "  a 1.0.0",
+            "    https://example.com/a",
+            "    Copyright (c) 2017 A",
+            "",
+            &rule('-'),
+            "Copyright (c) 2017 A",
+            "",
+            "Permission is hereby granted",
+            "",
+            &rule('='),
+            "MIT",
+            "",
+            "  b 0.2.0",
+            "    Copyright (c) 2017 B",
+            "",
+            &rule('-'),
+            "Copyright (c) 2017 B",
+            "",
+            "Permission is hereby granted",
+            "",
+        ];
+
+        assert_eq!(expected.join("\n"), String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn can_write_markdown() {
+        let mut reports = reports();
+        reports.truncate(2);
+        reports[0].package_name = "b_c".to_string();
+        reports[0].conclusion.as_mut().unwrap().full_license_document = "```\nfenced\n```".to_string();
+
+        let mut out = vec![];
+        write_markdown(&mut out, &reports, attribution::DEFAULT_TITLE).unwrap();
+
+        let expected = [
+            "# Third-party software notices",
+            "",
+            INTRODUCTION,
+            "",
+            "## MIT",
+            "",
+            " * [a 1.0.0](<https://example.com/a>)  ",
+            "   Copyright (c) 2017 A",
+            "",
+            "```",
+            "Copyright (c) 2017 A",
+            "",
+            "Permission is hereby granted",
+            "```",
+            "",
+            "## MIT",
+            "",
+            " * b\\_c 0.2.0  ",
+            "   Copyright (c) 2017 B",
+            "",
+            "````",
+            "```",
+            "fenced",
+            "```",
+            "````",
+            "",
+        ];
+
+        assert_eq!(expected.join("\n"), String::from_utf8(out).unwrap());
+    }
+}
@@ -0,0 +1,162 @@
+//! What the software bill of materials formats have in common
+
+use expression;
+use license::sha256_hex;
+use {LicenseError, LicenseReport};
+
+const CRATES_IO: &str = "registry+https://github.com/rust-lang/crates.io-index";
+
+pub const TOOL_NAME: &str = env!("CARGO_PKG_NAME");
+pub const TOOL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Identifies a crate among the reports, in the form used for
+/// `LicenseReport::dependencies`
+pub fn id_of(report: &LicenseReport) -> String {
+    format!("{} {}", report.package_name, report.version)
+}
+
+/// The package URL, as in `pkg:cargo/serde@1.0.0`
+pub fn purl(report: &LicenseReport) -> String {
+    format!("pkg:cargo/{}@{}", report.package_name, report.version)
+}
+
+/// Where the crate can be downloaded, if known. Git sources are given as
+/// `git+URL@COMMIT`.
+pub fn download_location(report: &LicenseReport) -> Option<String> {
+    let source = try_opt!(report.source.as_ref());
+
+    if source == CRATES_IO {
+        Some(format!("https://crates.io/api/v1/crates/{}/{}/download", report.package_name, report.version))
+    } else if source.starts_with("git+") {
+        let mut parts = source.splitn(2, '#');
+        let url = parts.next().unwrap();
+        let url = url.split('?').next().unwrap();
+
+        match parts.next() {
+            Some(commit) => Some(format!("{}@{}", url, commit)),
+            None => Some(url.to_string()),
+        }
+    } else {
+        None
+    }
+}
+
+/// Gives every report the identifier `id` gives it. Identifiers that
+/// would be shared by several crates, such as crates of the same name and
+/// version from different sources, get a short hash of the crate added after
+/// `separator`.
+pub fn unique_ids<F: Fn(&LicenseReport) -> String>(reports: &[LicenseReport], id: F, separator: &str) -> Vec<String> {
+    let ids = reports.iter().map(&id).collect::<Vec<_>>();
+
+    ids.iter().zip(reports)
+        .map(|(x, report)| {
+            if ids.iter().filter(|y| *y == x).count() > 1 {
+                let source = report.source.as_ref().map_or("", String::as_str);
+                let hash = sha256_hex(format!("{} {} {}", report.package_name, report.version, source));
+                format!("{}{}{}", x, separator, &hash[..8])
+            } else {
+                x.clone()
+            }
+        })
+        .collect()
+}
+
+/// The license of the crate manifest as written, if known
+fn declared(report: &LicenseReport) -> Option<&str> {
+    match report.conclusion {
+        Ok(ref description) => Some(&description.full_spdx_license),
+        Err(LicenseError::UnacceptableLicense(ref declared)) => Some(declared),
+        Err(_) => None,
+    }
+}
+
+/// The license expression from the crate manifest, if known and valid. The
+/// deprecated `MIT/Apache-2.0` syntax is given as `MIT OR Apache-2.0`.
+pub fn declared_license(report: &LicenseReport) -> Option<String> {
+    expression::parse(try_opt!(declared(report))).map(|x| x.to_string())
+}
+
+/// Like `declared_license`, but licenses that are not valid expressions are
+/// given as written, for formats meant to be read by people
+pub fn declared_text(report: &LicenseReport) -> Option<String> {
+    declared_license(report).or_else(|| declared(report).map(|x| x.to_string()))
+}
+
+/// The license the hound settled on, if any
+pub fn concluded_license(report: &LicenseReport) -> Option<&'static str> {
+    report.conclusion.as_ref().ok().map(|x| x.chosen_license.spdx_id())
+}
+
+pub fn copyright_notice(report: &LicenseReport) -> Option<&str> {
+    report.conclusion.as_ref().ok().map(|x| &x.copyright_notice[..])
+}
+
+/// Crates that no other crate depends on, typically the crates of the
+/// workspace itself
+pub fn roots(reports: &[LicenseReport]) -> Vec<&LicenseReport> {
+    reports.iter()
+        .filter(|report| {
+            let id = id_of(report);
+            !reports.iter().any(|x| x.dependencies.contains(&id))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use license::LicenseId;
+    use output::fixture;
+
+    #[test]
+    fn can_describe_packages() {
+        let mut report = fixture::ok("a", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 A");
+        report.conclusion.as_mut().unwrap().full_spdx_license = "MIT/Apache-2.0".to_string();
+
+        assert_eq!("pkg:cargo/a@1.0.0", purl(&report));
+        assert_eq!(Some("MIT OR Apache-2.0".to_string()), declared_license(&report));
+        assert_eq!(Some("MIT"), concluded_license(&report));
+        assert_eq!(
+            Some("https://crates.io/api/v1/crates/a/1.0.0/download".to_string()),
+            download_location(&report)
+        );
+
+        report.source = Some("git+https://github.com/owner/a?branch=next#0123abc".to_string());
+        assert_eq!(Some("git+https://github.com/owner/a@0123abc".to_string()), download_location(&report));
+
+        report.source = None;
+        assert_eq!(None, download_location(&report));
+
+        report.conclusion.as_mut().unwrap().full_spdx_license = "MIT, or Apache 2".to_string();
+        assert_eq!(None, declared_license(&report));
+        assert_eq!(Some("MIT, or Apache 2".to_string()), declared_text(&report));
+    }
+
+    #[test]
+    fn can_tell_crates_apart() {
+        let mut reports = vec![
+            fixture::ok("a", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 A"),
+            fixture::ok("a", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 A"),
+            fixture::ok("b", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 B"),
+        ];
+        reports[1].source = Some("registry+https://git.example.com/index".to_string());
+
+        let ids = unique_ids(&reports, id_of, "#");
+        assert_eq!("b 1.0.0", ids[2]);
+        assert!(ids[0].starts_with("a 1.0.0#"));
+        assert!(ids[1].starts_with("a 1.0.0#"));
+        assert_ne!(ids[0], ids[1]);
+    }
+
+    #[test]
+    fn can_find_roots() {
+        let mut reports = vec![
+            fixture::ok("app", "0.1.0", LicenseId::Mit, "Copyright (c) 2017 App"),
+            fixture::ok("a", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 A"),
+        ];
+        reports[0].dependencies.push("a 1.0.0".to_string());
+
+        let roots = roots(&reports).into_iter().map(|x| &x.package_name[..]).collect::<Vec<_>>();
+        assert_eq!(vec!["app"], roots);
+    }
+}
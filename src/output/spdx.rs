@@ -0,0 +1,357 @@
+//! SPDX 2.3 documents, in JSON or tag-value form
+
+use std::io::{self, Write};
+use std::ptr;
+
+use serde_json;
+
+use super::sbom::{self, TOOL_NAME, TOOL_VERSION};
+use license::sha256_hex;
+use timestamp;
+use LicenseReport;
+
+const NOASSERTION: &str = "NOASSERTION";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreationInfo {
+    created: String,
+    creators: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Checksum<'a> {
+    algorithm: &'static str,
+    checksum_value: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExternalRef {
+    reference_category: &'static str,
+    reference_type: &'static str,
+    reference_locator: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Package<'a> {
+    name: &'a str,
+    #[serde(rename = "SPDXID")]
+    spdx_id: String,
+    version_info: &'a str,
+    download_location: String,
+    files_analyzed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    homepage: Option<&'a str>,
+    checksums: Vec<Checksum<'a>>,
+    license_concluded: String,
+    license_declared: String,
+    copyright_text: String,
+    external_refs: Vec<ExternalRef>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Relationship {
+    spdx_element_id: String,
+    relationship_type: &'static str,
+    related_spdx_element: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Document<'a> {
+    spdx_version: &'static str,
+    data_license: &'static str,
+    #[serde(rename = "SPDXID")]
+    spdx_id: &'static str,
+    name: String,
+    document_namespace: String,
+    creation_info: CreationInfo,
+    packages: Vec<Package<'a>>,
+    relationships: Vec<Relationship>,
+}
+
+/// SPDX identifiers may only contain letters, numbers, `.` and `-`, so
+/// these are not unique by themselves. See `sbom::unique_ids`.
+fn spdx_ref(report: &LicenseReport) -> String {
+    let id = format!("{}-{}", report.package_name, report.version);
+    let id = id.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '-' })
+        .collect::<String>();
+
+    format!("SPDXRef-Package-{}", id)
+}
+
+fn package(report: &LicenseReport, spdx_id: String) -> Package {
+    let link = report.conclusion.as_ref().ok().and_then(|x| x.link.as_ref());
+
+    Package {
+        name: &report.package_name,
+        spdx_id,
+        version_info: &report.version,
+        download_location: sbom::download_location(report).unwrap_or_else(|| NOASSERTION.to_string()),
+        files_analyzed: false,
+        homepage: link.map(|x| &x[..]).filter(|x| x.starts_with("https://") || x.starts_with("http://")),
+        checksums: report.checksum.iter()
+            .map(|x| Checksum { algorithm: "SHA256", checksum_value: x })
+            .collect(),
+        license_concluded: sbom::concluded_license(report).unwrap_or(NOASSERTION).to_string(),
+        license_declared: sbom::declared_license(report).unwrap_or_else(|| NOASSERTION.to_string()),
+        copyright_text: sbom::copyright_notice(report).unwrap_or(NOASSERTION).to_string(),
+        external_refs: vec![ExternalRef {
+            reference_category: "PACKAGE-MANAGER",
+            reference_type: "purl",
+            reference_locator: sbom::purl(report),
+        }],
+    }
+}
+
+fn document<'a>(reports: &'a [LicenseReport], name: Option<&str>, created: String) -> Document<'a> {
+    let roots = sbom::roots(reports);
+
+    let spdx_refs = sbom::unique_ids(reports, spdx_ref, "-");
+    let ref_of = |report: &LicenseReport| spdx_refs[reports.iter().position(|x| ptr::eq(x, report)).unwrap()].clone();
+
+    let name = name.map(|x| x.to_string()).unwrap_or_else(||
+        roots.iter().map(|x| &x.package_name[..]).collect::<Vec<_>>().join(", ")
+    );
+
+    // The namespace must be unique for each document
+    let ids = reports.iter().map(sbom::id_of).collect::<Vec<_>>().join("\n");
+    let document_namespace = format!(
+        "https://spdx.org/spdxdocs/{}-{}",
+        TOOL_NAME,
+        sha256_hex(format!("{}\n{}\n{}", name, created, ids))
+    );
+
+    let mut relationships = roots.iter()
+        .map(|x| Relationship {
+            spdx_element_id: "SPDXRef-DOCUMENT".to_string(),
+            relationship_type: "DESCRIBES",
+            related_spdx_element: ref_of(x),
+        })
+        .collect::<Vec<_>>();
+
+    for report in reports {
+        for dependency in &report.dependencies {
+            if let Some(dependency) = reports.iter().find(|x| sbom::id_of(x) == *dependency) {
+                relationships.push(Relationship {
+                    spdx_element_id: ref_of(report),
+                    relationship_type: "DEPENDS_ON",
+                    related_spdx_element: ref_of(dependency),
+                });
+            }
+        }
+    }
+
+    Document {
+        spdx_version: "SPDX-2.3",
+        data_license: "CC0-1.0",
+        spdx_id: "SPDXRef-DOCUMENT",
+        name,
+        document_namespace,
+        creation_info: CreationInfo {
+            created,
+            creators: vec![format!("Tool: {}-{}", TOOL_NAME, TOOL_VERSION)],
+        },
+        packages: reports.iter().zip(spdx_refs.iter().cloned()).map(|(x, spdx_id)| package(x, spdx_id)).collect(),
+        relationships,
+    }
+}
+
+/// `name` defaults to the names of the crates no other crate depends on
+pub fn write_json<W: Write>(out: &mut W, reports: &[LicenseReport], name: Option<&str>) -> Result<(), serde_json::Error> {
+    serde_json::to_writer_pretty(out, &document(reports, name, timestamp::now()))
+}
+
+/// Free-form values are wrapped in `<text>` when they span several lines
+/// or could be mistaken for another tag
+fn text(value: &str) -> String {
+    if value.contains('\n') || value.contains(':') {
+        format!("<text>{}</text>", value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn write_tag_value_document<W: Write>(out: &mut W, document: &Document) -> io::Result<()> {
+    writeln!(out, "SPDXVersion: {}", document.spdx_version)?;
+    writeln!(out, "DataLicense: {}", document.data_license)?;
+    writeln!(out, "SPDXID: {}", document.spdx_id)?;
+    writeln!(out, "DocumentName: {}", document.name)?;
+    writeln!(out, "DocumentNamespace: {}", document.document_namespace)?;
+    for creator in &document.creation_info.creators {
+        writeln!(out, "Creator: {}", creator)?;
+    }
+    writeln!(out, "Created: {}", document.creation_info.created)?;
+
+    for package in &document.packages {
+        writeln!(out)?;
+        writeln!(out, "PackageName: {}", package.name)?;
+        writeln!(out, "SPDXID: {}", package.spdx_id)?;
+        writeln!(out, "PackageVersion: {}", package.version_info)?;
+        writeln!(out, "PackageDownloadLocation: {}", package.download_location)?;
+        writeln!(out, "FilesAnalyzed: {}", package.files_analyzed)?;
+        if let Some(homepage) = package.homepage {
+            writeln!(out, "PackageHomePage: {}", homepage)?;
+        }
+        for checksum in &package.checksums {
+            writeln!(out, "PackageChecksum: {}: {}", checksum.algorithm, checksum.checksum_value)?;
+        }
+        writeln!(out, "PackageLicenseConcluded: {}", package.license_concluded)?;
+        writeln!(out, "PackageLicenseDeclared: {}", package.license_declared)?;
+        writeln!(out, "PackageCopyrightText: {}", text(&package.copyright_text))?;
+        for external_ref in &package.external_refs {
+            writeln!(
+                out, "ExternalRef: {} {} {}",
+                external_ref.reference_category, external_ref.reference_type, external_ref.reference_locator
+            )?;
+        }
+    }
+
+    if !document.relationships.is_empty() {
+        writeln!(out)?;
+    }
+    for relationship in &document.relationships {
+        writeln!(
+            out, "Relationship: {} {} {}",
+            relationship.spdx_element_id, relationship.relationship_type, relationship.related_spdx_element
+        )?;
+    }
+
+    Ok(())
+}
+
+/// `name` defaults to the names of the crates no other crate depends on
+pub fn write_tag_value<W: Write>(out: &mut W, reports: &[LicenseReport], name: Option<&str>) -> io::Result<()> {
+    write_tag_value_document(out, &document(reports, name, timestamp::now()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use license::LicenseId;
+    use output::fixture;
+
+    fn reports() -> Vec<LicenseReport> {
+        let mut reports = vec![
+            fixture::ok("app", "0.1.0", LicenseId::Mit, "Copyright (c) 2017 App"),
+            fixture::ok("serde_json", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 Serde: JSON"),
+            fixture::unrecoverable("b"),
+        ];
+        reports[0].source = None;
+        reports[0].dependencies = vec!["serde_json 1.0.0".to_string(), "b 1.0.0".to_string()];
+        reports[1].checksum = Some("abc".to_string());
+        reports[1].conclusion.as_mut().unwrap().full_spdx_license = "MIT/Apache-2.0".to_string();
+        reports
+    }
+
+    #[test]
+    fn can_write_json() {
+        let reports = reports();
+        let document = serde_json::to_value(&document(&reports, None, "2017-11-04T13:37:00Z".to_string())).unwrap();
+
+        assert_eq!("SPDX-2.3", document["spdxVersion"]);
+        assert_eq!("app", document["name"]);
+        assert!(document["documentNamespace"].as_str().unwrap().starts_with(&format!("https://spdx.org/spdxdocs/{}-", TOOL_NAME)));
+        assert_eq!("2017-11-04T13:37:00Z", document["creationInfo"]["created"]);
+
+        let package = &document["packages"][1];
+        assert_eq!("serde_json", package["name"]);
+        assert_eq!("SPDXRef-Package-serde-json-1.0.0", package["SPDXID"]);
+        assert_eq!("1.0.0", package["versionInfo"]);
+        assert_eq!("https://crates.io/api/v1/crates/serde_json/1.0.0/download", package["downloadLocation"]);
+        assert_eq!("MIT", package["licenseConcluded"]);
+        assert_eq!("MIT OR Apache-2.0", package["licenseDeclared"]);
+        assert_eq!("Copyright (c) 2017 Serde: JSON", package["copyrightText"]);
+        assert_eq!(json!([{ "algorithm": "SHA256", "checksumValue": "abc" }]), package["checksums"]);
+        assert_eq!("pkg:cargo/serde_json@1.0.0", package["externalRefs"][0]["referenceLocator"]);
+
+        let unrecoverable = &document["packages"][2];
+        assert_eq!("NOASSERTION", unrecoverable["licenseConcluded"]);
+        assert_eq!("NOASSERTION", unrecoverable["copyrightText"]);
+
+        assert_eq!("NOASSERTION", document["packages"][0]["downloadLocation"]);
+
+        assert_eq!(
+            json!([
+                {
+                    "spdxElementId": "SPDXRef-DOCUMENT",
+                    "relationshipType": "DESCRIBES",
+                    "relatedSpdxElement": "SPDXRef-Package-app-0.1.0",
+                },
+                {
+                    "spdxElementId": "SPDXRef-Package-app-0.1.0",
+                    "relationshipType": "DEPENDS_ON",
+                    "relatedSpdxElement": "SPDXRef-Package-serde-json-1.0.0",
+                },
+                {
+                    "spdxElementId": "SPDXRef-Package-app-0.1.0",
+                    "relationshipType": "DEPENDS_ON",
+                    "relatedSpdxElement": "SPDXRef-Package-b-1.0.0",
+                },
+            ]),
+            document["relationships"]
+        );
+    }
+
+    #[test]
+    fn gives_every_package_its_own_id() {
+        let mut reports = vec![
+            fixture::ok("serde_json", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 A"),
+            fixture::ok("serde-json", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 B"),
+            fixture::ok("serde-json", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 C"),
+        ];
+        reports[2].source = Some("registry+https://git.example.com/index".to_string());
+        reports[2].conclusion.as_mut().unwrap().full_spdx_license = "MIT or Apache 2".to_string();
+
+        let document = serde_json::to_value(&document(&reports, None, "2017-11-04T13:37:00Z".to_string())).unwrap();
+
+        let mut ids = document["packages"].as_array().unwrap().iter()
+            .map(|x| x["SPDXID"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert!(ids.iter().all(|x| x.starts_with("SPDXRef-Package-serde-json-1.0.0-")));
+        ids.sort();
+        ids.dedup();
+        assert_eq!(3, ids.len());
+
+        assert_eq!("NOASSERTION", document["packages"][2]["licenseDeclared"]);
+    }
+
+    #[test]
+    fn can_write_tag_value() {
+        let reports = reports();
+        let document = document(&reports, Some("Our product"), "2017-11-04T13:37:00Z".to_string());
+
+        let mut out = vec![];
+        write_tag_value_document(&mut out, &document).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.starts_with("SPDXVersion: SPDX-2.3\nDataLicense: CC0-1.0\nSPDXID: SPDXRef-DOCUMENT\nDocumentName: Our product\n"));
+        assert!(out.contains(&format!("\nCreator: Tool: {}-{}\n", TOOL_NAME, TOOL_VERSION)));
+
+        assert!(out.contains(
+            "\n\
+            PackageName: serde_json\n\
+            SPDXID: SPDXRef-Package-serde-json-1.0.0\n\
+            PackageVersion: 1.0.0\n\
+            PackageDownloadLocation: https://crates.io/api/v1/crates/serde_json/1.0.0/download\n\
+            FilesAnalyzed: false\n\
+            PackageChecksum: SHA256: abc\n\
+            PackageLicenseConcluded: MIT\n\
+            PackageLicenseDeclared: MIT OR Apache-2.0\n\
+            PackageCopyrightText: <text>Copyright (c) 2017 Serde: JSON</text>\n\
+            ExternalRef: PACKAGE-MANAGER purl pkg:cargo/serde_json@1.0.0\n"
+        ));
+
+        assert!(out.ends_with(
+            "\n\
+            Relationship: SPDXRef-DOCUMENT DESCRIBES SPDXRef-Package-app-0.1.0\n\
+            Relationship: SPDXRef-Package-app-0.1.0 DEPENDS_ON SPDXRef-Package-serde-json-1.0.0\n\
+            Relationship: SPDXRef-Package-app-0.1.0 DEPENDS_ON SPDXRef-Package-b-1.0.0\n"
+        ));
+    }
+}
@@ -0,0 +1,122 @@
+//! Attribution documents in any format, rendered from a user-supplied
+//! Handlebars template
+
+use std::io::Write;
+use std::path::Path;
+
+use handlebars::{self, Handlebars, TemplateRenderError};
+use serde_json;
+
+use super::attribution::{self, Attribution, INTRODUCTION};
+use report::Crate;
+use timestamp;
+use LicenseReport;
+
+handlebars_helper!(json: |x: Json| serde_json::to_string(x).unwrap());
+
+#[derive(Serialize)]
+struct Group<'a> {
+    license: &'static str,
+    license_text: String,
+    crates: Vec<Attribution<'a>>,
+}
+
+/// What the template is rendered with
+#[derive(Serialize)]
+struct Model<'a> {
+    title: &'a str,
+    introduction: &'a str,
+    generated_at: String,
+
+    /// Crates with a recovered license, grouped by license text
+    groups: Vec<Group<'a>>,
+
+    /// Names of the crates whose licenses could not be recovered
+    missing: Vec<&'a str>,
+
+    /// The `crates` of the full report, as written by `--format json`
+    reports: Vec<Crate>,
+}
+
+/// Templates for HTML, named like `*.html` or `*.html.hbs`
+fn is_html(template_path: &Path) -> bool {
+    let name = template_path.file_name().and_then(|x| x.to_str()).unwrap_or("");
+    let name = name.trim_end_matches(".hbs");
+
+    name.ends_with(".html") || name.ends_with(".htm")
+}
+
+/// Renders `template` to `out`. Values are HTML escaped in templates for
+/// HTML, and inserted as they are otherwise.
+pub fn write<W: Write>(out: &mut W, reports: &[LicenseReport], title: &str, template_path: &Path, template: &str) -> Result<(), TemplateRenderError> {
+    let mut registry = Handlebars::new();
+    registry.register_helper("json", Box::new(json));
+
+    if !is_html(template_path) {
+        registry.register_escape_fn(handlebars::no_escape);
+    }
+
+    let model = Model {
+        title,
+        introduction: INTRODUCTION,
+        generated_at: timestamp::now(),
+        groups: attribution::group(reports).into_iter()
+            .map(|x| Group { license: x.license.spdx_id(), license_text: x.license_text, crates: x.crates })
+            .collect(),
+        missing: attribution::missing(reports).into_iter()
+            .map(|x| &x.package_name[..])
+            .collect(),
+        reports: reports.iter().map(Crate::from).collect(),
+    };
+
+    registry.render_template_to_write(template, &model, out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use license::LicenseId;
+    use output::fixture;
+
+    fn render(template_path: &str, template: &str) -> String {
+        let reports = vec![
+            fixture::ok("a", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 \"A\" <a@example.com>"),
+            fixture::unrecoverable("b"),
+        ];
+
+        let mut out = vec![];
+        write(&mut out, &reports, "Notices", Path::new(template_path), template).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn can_render_groups() {
+        let template = "{{title}}\n\
+            {{#each groups}}[{{license}}]{{#each crates}} {{name}} {{version}}: {{copyright_notice}}{{/each}}\n{{/each}}\
+            missing:{{#each missing}} {{this}}{{/each}}\n\
+            {{reports.[0].license.source.origin.path}}";
+
+        assert_eq!(
+            "Notices\n\
+            [MIT] a 1.0.0: Copyright (c) 2017 \"A\" <a@example.com>\n\
+            missing: b\n\
+            LICENSE",
+            render("notices.txt.hbs", template)
+        );
+    }
+
+    #[test]
+    fn can_escape_values() {
+        let template = "{{#each groups}}{{#each crates}}{{copyright_notice}} {{json copyright_notice}}{{/each}}{{/each}}";
+
+        assert_eq!(
+            "Copyright (c) 2017 \"A\" <a@example.com> \"Copyright (c) 2017 \\\"A\\\" <a@example.com>\"",
+            render("notices.json", template)
+        );
+
+        assert_eq!(
+            "Copyright (c) 2017 &quot;A&quot; &lt;a@example.com&gt;",
+            render("notices.html.hbs", "{{#each groups}}{{#each crates}}{{copyright_notice}}{{/each}}{{/each}}")
+        );
+    }
+}
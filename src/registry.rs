@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use cargo::core::SourceId;
+use cargo::util::{CargoError, Config, ConfigValue};
+use toml;
+
+use read_file;
+
+pub const CRATES_IO: &str = "crates-io";
+
+#[derive(Debug)]
+pub enum Error {
+    Cargo(CargoError),
+    Credentials(PathBuf, toml::de::Error),
+}
+
+impl From<CargoError> for Error {
+    fn from(other: CargoError) -> Error {
+        Error::Cargo(other)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Error::Cargo(ref err) => write!(f, "Invalid Cargo configuration: {}", err),
+            &Error::Credentials(ref path, ref err) => write!(f, "Invalid credentials in {}: {}", path.display(), err),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct Credentials {
+    #[serde(default)]
+    registries: HashMap<String, RegistryCredentials>,
+}
+
+#[derive(Deserialize)]
+struct RegistryCredentials {
+    token: Option<String>,
+}
+
+/// Names of the registries configured in Cargo's configuration files
+/// (`[registries.<name>]` in `.cargo/config`), keyed by index URL, and
+/// their tokens.
+pub struct Registries {
+    names: HashMap<String, String>,
+    tokens: HashMap<String, String>,
+}
+
+fn normalize_url(url: &str) -> String {
+    url.trim_end_matches('/').to_string()
+}
+
+impl Registries {
+    /// Reads the registries of Cargo's configuration, along with the tokens
+    /// in Cargo's credentials file
+    pub fn from_config(config: &Config) -> Result<Registries, Error> {
+        let mut registries = match config.get_table("registries")? {
+            Some(registries) => Registries::from_table(&registries.val),
+            None => Registries { names: HashMap::new(), tokens: HashMap::new() },
+        };
+
+        for file_name in &["credentials", "credentials.toml"] {
+            let path = config.home().join(file_name).into_path_unlocked();
+            if let Ok(text) = read_file(&path) {
+                registries.add_credentials(&text).map_err(|err| Error::Credentials(path, err))?;
+            }
+        }
+
+        Ok(registries)
+    }
+
+    /// Reads the `[registries]` table of Cargo's configuration. Entries
+    /// without an `index` are left out.
+    fn from_table(registries: &HashMap<String, ConfigValue>) -> Registries {
+        let mut names = HashMap::new();
+        let mut tokens = HashMap::new();
+
+        for (name, value) in registries {
+            if let ConfigValue::Table(ref table, _) = *value {
+                if let Some(&ConfigValue::String(ref index, _)) = table.get("index") {
+                    names.insert(normalize_url(index), name.clone());
+                }
+                if let Some(&ConfigValue::String(ref token, _)) = table.get("token") {
+                    tokens.insert(name.clone(), token.clone());
+                }
+            }
+        }
+
+        Registries { names, tokens }
+    }
+
+    /// Adds the tokens of Cargo's credentials file. Tokens given in the
+    /// configuration take precedence.
+    fn add_credentials(&mut self, text: &str) -> Result<(), toml::de::Error> {
+        let credentials: Credentials = toml::from_str(text)?;
+
+        for (name, registry) in credentials.registries {
+            if let Some(token) = registry.token {
+                self.tokens.entry(name).or_insert(token);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The name of the registry a package was fetched from, or `None` for
+    /// packages that do not come from a registry, such as git dependencies.
+    ///
+    /// Registries that are not named in the configuration are identified
+    /// by their index URL.
+    pub fn name_of(&self, source_id: &SourceId) -> Option<String> {
+        if !source_id.is_registry() {
+            return None;
+        }
+
+        if source_id.is_default_registry() {
+            return Some(CRATES_IO.to_string());
+        }
+
+        Some(self.name_of_index(source_id.url().as_str()))
+    }
+
+    /// The token for downloading crates from the registry of `source_id`,
+    /// if it requires one. Crates from crates.io are downloaded by Cargo.
+    pub fn token_of(&self, source_id: &SourceId) -> Option<&str> {
+        if !source_id.is_registry() || source_id.is_default_registry() {
+            return None;
+        }
+
+        self.tokens.get(&self.name_of_index(source_id.url().as_str())).map(|x| &**x)
+    }
+
+    /// The name of the registry with the index at `url`, or the URL itself
+    /// if it is not named in the configuration
+    fn name_of_index(&self, url: &str) -> String {
+        let url = normalize_url(url);
+        self.names.get(&url).cloned().unwrap_or(url)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn registry(index: &str) -> ConfigValue {
+        let mut table = HashMap::new();
+        table.insert("index".to_string(), ConfigValue::String(index.to_string(), PathBuf::from(".cargo/config")));
+        ConfigValue::Table(table, PathBuf::from(".cargo/config"))
+    }
+
+    fn with_token(registry: ConfigValue, token: &str) -> ConfigValue {
+        match registry {
+            ConfigValue::Table(mut table, path) => {
+                table.insert("token".to_string(), ConfigValue::String(token.to_string(), path.clone()));
+                ConfigValue::Table(table, path)
+            },
+            x => x,
+        }
+    }
+
+    #[test]
+    fn can_name_configured_registries() {
+        let mut table = HashMap::new();
+        table.insert("our-registry".to_string(), registry("https://git.example.com/index/"));
+        table.insert("no-index".to_string(), ConfigValue::Table(HashMap::new(), PathBuf::from(".cargo/config")));
+        table.insert("not-a-table".to_string(), ConfigValue::Boolean(true, PathBuf::from(".cargo/config")));
+
+        let registries = Registries::from_table(&table);
+
+        assert_eq!(1, registries.names.len());
+        assert_eq!("our-registry", registries.name_of_index("https://git.example.com/index"));
+        assert_eq!("our-registry", registries.name_of_index("https://git.example.com/index/"));
+        assert_eq!("https://other.example.com/index", registries.name_of_index("https://other.example.com/index/"));
+    }
+
+    #[test]
+    fn can_read_registry_tokens() {
+        let mut table = HashMap::new();
+        table.insert("configured".to_string(), with_token(registry("https://configured.example.com/index"), "from-config"));
+        table.insert("credentials".to_string(), registry("https://credentials.example.com/index"));
+        table.insert("public".to_string(), registry("https://public.example.com/index"));
+
+        let mut registries = Registries::from_table(&table);
+        registries.add_credentials(r#"
+            [registry]
+            token = "crates-io"
+
+            [registries.configured]
+            token = "ignored"
+
+            [registries.credentials]
+            token = "from-credentials"
+        "#).unwrap();
+
+        assert_eq!(Some(&"from-config".to_string()), registries.tokens.get("configured"));
+        assert_eq!(Some(&"from-credentials".to_string()), registries.tokens.get("credentials"));
+        assert_eq!(None, registries.tokens.get("public"));
+
+        assert!(registries.add_credentials("[registries.x]\ntoken = 1").is_err());
+    }
+}
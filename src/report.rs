@@ -0,0 +1,493 @@
+//! The JSON report, in a documented and versioned form
+//!
+//! The document is described by `schema/report-v1.schema.json`. Fields may
+//! be added without changing `SCHEMA_VERSION`, but fields are never removed,
+//! renamed or given a different meaning without bumping it.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use serde_json::{self, Value};
+
+use license::{self, Category, LicenseId, LicenseOrigin, LicenseSource};
+use output::Status;
+use output::sbom::{TOOL_NAME, TOOL_VERSION};
+use timestamp;
+use {LicenseDescription, LicenseError, LicenseReport};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The license reports of one run, with what is known about the run
+#[derive(Debug)]
+pub struct Report {
+    /// When the reports were made, as an RFC 3339 timestamp
+    pub generated_at: String,
+
+    /// SHA-256 of the `Cargo.lock` the reports were made from, in lowercase
+    /// hex
+    pub lock_file_sha256: Option<String>,
+
+    pub crates: Vec<LicenseReport>,
+}
+
+impl Report {
+    /// Reports made just now from a lock file with the given contents
+    pub fn new(lock_file: &str, crates: Vec<LicenseReport>) -> Report {
+        Report {
+            generated_at: timestamp::now(),
+            lock_file_sha256: Some(license::sha256_hex(lock_file)),
+            crates,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Deserializing(serde_json::Error),
+
+    /// The report was written with a schema version this version of the
+    /// tool does not know, or is not a report at all
+    UnsupportedSchemaVersion(Option<u64>),
+
+    UnknownLicense(String),
+    UnknownErrorKind(String),
+
+    /// The crate has neither a license nor an error
+    MissingConclusion(String),
+}
+
+impl From<serde_json::Error> for LoadError {
+    fn from(other: serde_json::Error) -> LoadError {
+        LoadError::Deserializing(other)
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &LoadError::Deserializing(ref err) => write!(f, "Not a valid report: {}", err),
+            &LoadError::UnsupportedSchemaVersion(Some(version)) =>
+                write!(f, "Unsupported schema_version {}. Expected {}", version, SCHEMA_VERSION),
+            &LoadError::UnsupportedSchemaVersion(None) =>
+                write!(f, "Not a report: schema_version is missing"),
+            &LoadError::UnknownLicense(ref license) => write!(f, "Unknown license {:?}", license),
+            &LoadError::UnknownErrorKind(ref kind) => write!(f, "Unknown error kind {:?}", kind),
+            &LoadError::MissingConclusion(ref name) => write!(f, "{} has neither a license nor an error", name),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Document {
+    pub schema_version: u32,
+    pub tool: Tool,
+    pub generated_at: String,
+    pub lock_file_sha256: Option<String>,
+    pub crates: Vec<Crate>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Tool {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Crate {
+    pub name: String,
+    pub version: String,
+    pub registry: Option<String>,
+    pub source: Option<String>,
+    pub checksum: Option<String>,
+    pub dependencies: Vec<String>,
+
+    /// `ok`, `warn` or `error`. Derived from `license`, `error` and
+    /// `findings`, for the convenience of readers
+    pub status: String,
+
+    /// Present unless `error` is
+    pub license: Option<License>,
+    pub error: Option<Error>,
+    pub findings: Vec<Finding>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct License {
+    /// The SPDX identifier of the license the hound settled on
+    pub chosen: String,
+
+    /// The category of the chosen license. Absent in reports written before
+    /// it was added
+    #[serde(default)]
+    pub category: Option<Category>,
+
+    /// The license expression from the crate manifest
+    pub declared: String,
+
+    pub copyright_notice: String,
+    pub text: String,
+    pub link: Option<String>,
+    pub source: Source,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Source {
+    pub origin: Origin,
+    pub sha256: String,
+    pub fetched_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum Origin {
+    #[serde(rename = "crate")]
+    Crate { path: String },
+
+    #[serde(rename = "git")]
+    Git { url: String, commit: String, path: String },
+
+    #[serde(rename = "github-api")]
+    GitHubApi { url: String },
+
+    #[serde(rename = "github-repo")]
+    GitHubRepo { url: String, git_ref: String },
+
+    #[serde(rename = "forge-repo")]
+    ForgeRepo { forge: String, url: String, git_ref: String },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Error {
+    /// As given by `LicenseError::kind`
+    pub kind: String,
+    pub detail: Option<String>,
+}
+
+/// Tagged with the `kind` given by `license::Finding::kind`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum Finding {
+    LicenseMismatch {
+        service: String,
+        url: String,
+        detected_license: String,
+        chosen_license: String,
+        license_text: String,
+    },
+}
+
+/// Unlike `LicenseError::detail`, this includes the license text for
+/// `UnableToRecoverAttribution`, so nothing is lost
+fn error_detail(err: &LicenseError) -> Option<String> {
+    match err {
+        &LicenseError::UnableToRecoverAttribution(ref license_text) => Some(license_text.clone()),
+        _ => err.detail(),
+    }
+}
+
+impl<'a> From<&'a LicenseOrigin> for Origin {
+    fn from(origin: &LicenseOrigin) -> Origin {
+        match origin.clone() {
+            LicenseOrigin::Crate(path) => Origin::Crate { path },
+            LicenseOrigin::Git { url, commit, path } => Origin::Git { url, commit, path },
+            LicenseOrigin::GitHubApi { url } => Origin::GitHubApi { url },
+            LicenseOrigin::GitHubRepo { url, git_ref } => Origin::GitHubRepo { url, git_ref },
+            LicenseOrigin::ForgeRepo { forge, url, git_ref } => Origin::ForgeRepo { forge, url, git_ref },
+        }
+    }
+}
+
+impl<'a> From<&'a license::Finding> for Finding {
+    fn from(finding: &license::Finding) -> Finding {
+        match finding.clone() {
+            license::Finding::LicenseMismatch { service, url, detected_license, chosen_license, license_text } =>
+                Finding::LicenseMismatch {
+                    service,
+                    url,
+                    detected_license,
+                    chosen_license: chosen_license.spdx_id().to_string(),
+                    license_text,
+                },
+        }
+    }
+}
+
+impl<'a> From<&'a LicenseReport> for Crate {
+    fn from(report: &LicenseReport) -> Crate {
+        let (license, error) = match report.conclusion {
+            Ok(ref description) => (
+                Some(License {
+                    chosen: description.chosen_license.spdx_id().to_string(),
+                    category: Some(description.chosen_license.category()),
+                    declared: description.full_spdx_license.clone(),
+                    copyright_notice: description.copyright_notice.clone(),
+                    text: description.full_license_document.clone(),
+                    link: description.link.clone(),
+                    source: Source {
+                        origin: Origin::from(&description.license_source.origin),
+                        sha256: description.license_source.sha256.clone(),
+                        fetched_at: description.license_source.fetched_at.clone(),
+                    },
+                }),
+                None,
+            ),
+            Err(ref err) => (
+                None,
+                Some(Error {
+                    kind: err.kind().to_string(),
+                    detail: error_detail(err),
+                }),
+            ),
+        };
+
+        Crate {
+            name: report.package_name.clone(),
+            version: report.version.clone(),
+            registry: report.registry.clone(),
+            source: report.source.clone(),
+            checksum: report.checksum.clone(),
+            dependencies: report.dependencies.clone(),
+            status: Status::of(report).label().to_lowercase(),
+            license,
+            error,
+            findings: report.findings.iter().map(Finding::from).collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a Report> for Document {
+    fn from(report: &Report) -> Document {
+        Document {
+            schema_version: SCHEMA_VERSION,
+            tool: Tool {
+                name: TOOL_NAME.to_string(),
+                version: TOOL_VERSION.to_string(),
+            },
+            generated_at: report.generated_at.clone(),
+            lock_file_sha256: report.lock_file_sha256.clone(),
+            crates: report.crates.iter().map(Crate::from).collect(),
+        }
+    }
+}
+
+fn license_id(spdx_id: &str) -> Result<LicenseId, LoadError> {
+    LicenseId::from_spdx_id(spdx_id).ok_or_else(|| LoadError::UnknownLicense(spdx_id.to_string()))
+}
+
+fn license_error(error: Error) -> Result<LicenseError, LoadError> {
+    let detail = error.detail.unwrap_or_default();
+
+    Ok(match &error.kind[..] {
+        "NoSource" => LicenseError::NoSource,
+        "LicenseNotDeclared" => LicenseError::LicenseNotDeclared(PathBuf::from(detail)),
+        "UnableToRecoverLicenseFile" => LicenseError::UnableToRecoverLicenseFile(PathBuf::from(detail)),
+        "UnableToRecoverAttribution" => LicenseError::UnableToRecoverAttribution(detail),
+        "UnacceptableLicense" => LicenseError::UnacceptableLicense(detail),
+        "RegistryNotAudited" => LicenseError::RegistryNotAudited(detail),
+        "LicenseMismatch" => LicenseError::LicenseMismatch(detail),
+        _ => return Err(LoadError::UnknownErrorKind(error.kind)),
+    })
+}
+
+impl From<Origin> for LicenseOrigin {
+    fn from(origin: Origin) -> LicenseOrigin {
+        match origin {
+            Origin::Crate { path } => LicenseOrigin::Crate(path),
+            Origin::Git { url, commit, path } => LicenseOrigin::Git { url, commit, path },
+            Origin::GitHubApi { url } => LicenseOrigin::GitHubApi { url },
+            Origin::GitHubRepo { url, git_ref } => LicenseOrigin::GitHubRepo { url, git_ref },
+            Origin::ForgeRepo { forge, url, git_ref } => LicenseOrigin::ForgeRepo { forge, url, git_ref },
+        }
+    }
+}
+
+impl Finding {
+    fn into_finding(self) -> Result<license::Finding, LoadError> {
+        match self {
+            Finding::LicenseMismatch { service, url, detected_license, chosen_license, license_text } =>
+                Ok(license::Finding::LicenseMismatch {
+                    service,
+                    url,
+                    detected_license,
+                    chosen_license: license_id(&chosen_license)?,
+                    license_text,
+                }),
+        }
+    }
+}
+
+impl Crate {
+    fn into_report(self) -> Result<LicenseReport, LoadError> {
+        let conclusion = match (self.license, self.error) {
+            (Some(license), _) => Ok(LicenseDescription {
+                chosen_license: license_id(&license.chosen)?,
+                copyright_notice: license.copyright_notice,
+                full_spdx_license: license.declared,
+                full_license_document: license.text,
+                license_source: LicenseSource {
+                    origin: LicenseOrigin::from(license.source.origin),
+                    sha256: license.source.sha256,
+                    fetched_at: license.source.fetched_at,
+                },
+                link: license.link,
+            }),
+            (None, Some(error)) => Err(license_error(error)?),
+            (None, None) => return Err(LoadError::MissingConclusion(self.name)),
+        };
+
+        Ok(LicenseReport {
+            package_name: self.name,
+            version: self.version,
+            registry: self.registry,
+            source: self.source,
+            checksum: self.checksum,
+            dependencies: self.dependencies,
+            findings: self.findings.into_iter().map(Finding::into_finding).collect::<Result<_, _>>()?,
+            conclusion,
+        })
+    }
+}
+
+impl Report {
+    /// Reads a report as written by `--format json`. The schema version is
+    /// checked first, so reports from incompatible versions of the tool are
+    /// told apart from broken ones.
+    pub fn from_json(json: &str) -> Result<Report, LoadError> {
+        let value: Value = serde_json::from_str(json)?;
+
+        match value.get("schema_version").and_then(|x| x.as_u64()) {
+            Some(version) if version == SCHEMA_VERSION as u64 => {},
+            version => return Err(LoadError::UnsupportedSchemaVersion(version)),
+        }
+
+        let document: Document = serde_json::from_value(value)?;
+
+        Ok(Report {
+            generated_at: document.generated_at,
+            lock_file_sha256: document.lock_file_sha256,
+            crates: document.crates.into_iter().map(Crate::into_report).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use jsonschema::JSONSchema;
+    use output::fixture;
+
+    const SCHEMA: &str = include_str!("../schema/report-v1.schema.json");
+
+    fn report() -> Report {
+        let mut crates = vec![
+            fixture::ok("a", "1.0.0", LicenseId::Mit, "Copyright (c) 2017 A"),
+            fixture::ok("b", "0.1.0", LicenseId::Bsd3Clause, "Copyright (c) 2017 B"),
+            fixture::unrecoverable("c"),
+        ];
+        crates[0].dependencies.push("b 0.1.0".to_string());
+        crates[0].checksum = Some("0123abcd".to_string());
+        crates[1].conclusion.as_mut().unwrap().license_source.origin = LicenseOrigin::Git {
+            url: "https://github.com/owner/b".to_string(),
+            commit: "0123abc".to_string(),
+            path: "LICENSE".to_string(),
+        };
+        crates[1].findings.push(license::Finding::LicenseMismatch {
+            service: "GitHub".to_string(),
+            url: "https://api.github.com/repos/owner/b/license".to_string(),
+            detected_license: "Apache-2.0".to_string(),
+            chosen_license: LicenseId::Bsd3Clause,
+            license_text: "Apache License".to_string(),
+        });
+
+        Report {
+            generated_at: "2017-11-04T13:37:00Z".to_string(),
+            lock_file_sha256: Some(license::sha256_hex("")),
+            crates,
+        }
+    }
+
+    #[test]
+    fn can_describe_crates() {
+        let document = serde_json::to_value(Document::from(&report())).unwrap();
+
+        assert_eq!(json!(SCHEMA_VERSION), document["schema_version"]);
+        assert_eq!(json!("ok"), document["crates"][0]["status"]);
+        assert_eq!(json!("MIT"), document["crates"][0]["license"]["chosen"]);
+        assert_eq!(json!("permissive"), document["crates"][0]["license"]["category"]);
+        assert_eq!(json!({ "kind": "crate", "path": "LICENSE" }), document["crates"][0]["license"]["source"]["origin"]);
+        assert_eq!(json!("warn"), document["crates"][1]["status"]);
+        assert_eq!(json!("git"), document["crates"][1]["license"]["source"]["origin"]["kind"]);
+        assert_eq!(json!("LicenseMismatch"), document["crates"][1]["findings"][0]["kind"]);
+        assert_eq!(json!("BSD-3-Clause"), document["crates"][1]["findings"][0]["chosen_license"]);
+        assert_eq!(json!(null), document["crates"][2]["license"]);
+        assert_eq!(
+            json!({ "kind": "UnableToRecoverLicenseFile", "detail": "c/Cargo.toml" }),
+            document["crates"][2]["error"]
+        );
+    }
+
+    #[test]
+    fn generated_documents_follow_the_schema() {
+        let schema: Value = serde_json::from_str(SCHEMA).unwrap();
+        let schema = JSONSchema::compile(&schema).unwrap();
+
+        let document = serde_json::to_value(Document::from(&report())).unwrap();
+        if let Err(errors) = schema.validate(&document) {
+            let errors = errors.map(|x| format!("{} at {}", x, x.instance_path)).collect::<Vec<_>>();
+            panic!("The document does not follow the schema:\n{}", errors.join("\n"));
+        }
+
+        let mut unknown = document.clone();
+        unknown["crates"][0]["license"]["chosen"] = json!("Bsd3Clause");
+        assert!(!schema.is_valid(&unknown));
+    }
+
+    #[test]
+    fn can_read_generated_documents() {
+        let json = serde_json::to_string(&Document::from(&report())).unwrap();
+        let read = Report::from_json(&json).unwrap();
+
+        assert_eq!(json, serde_json::to_string(&Document::from(&read)).unwrap());
+    }
+
+    #[test]
+    fn kinds_match_the_rest_of_the_output() {
+        let origins = vec![
+            LicenseOrigin::Crate("LICENSE".to_string()),
+            LicenseOrigin::Git { url: "".to_string(), commit: "".to_string(), path: "".to_string() },
+            LicenseOrigin::GitHubApi { url: "".to_string() },
+            LicenseOrigin::GitHubRepo { url: "".to_string(), git_ref: "".to_string() },
+            LicenseOrigin::ForgeRepo { forge: "gitlab".to_string(), url: "".to_string(), git_ref: "".to_string() },
+        ];
+        for origin in &origins {
+            assert_eq!(json!(origin.kind()), serde_json::to_value(Origin::from(origin)).unwrap()["kind"]);
+        }
+
+        let errors = vec![
+            LicenseError::NoSource,
+            LicenseError::LicenseNotDeclared(PathBuf::from("Cargo.toml")),
+            LicenseError::UnableToRecoverLicenseFile(PathBuf::from("LICENSE")),
+            LicenseError::UnableToRecoverAttribution("".to_string()),
+            LicenseError::UnacceptableLicense("GPL-3.0".to_string()),
+            LicenseError::RegistryNotAudited("crates-io".to_string()),
+            LicenseError::LicenseMismatch("Apache-2.0".to_string()),
+        ];
+        for err in &errors {
+            let error = Error { kind: err.kind().to_string(), detail: error_detail(err) };
+            assert_eq!(err.kind(), license_error(error).unwrap().kind());
+        }
+    }
+
+    #[test]
+    fn rejects_other_schema_versions() {
+        match Report::from_json(r#"{ "schema_version": 2 }"#) {
+            Err(LoadError::UnsupportedSchemaVersion(Some(2))) => {},
+            x => panic!("Unexpected result: {:?}", x),
+        }
+
+        match Report::from_json(r#"[{ "package_name": "a" }]"#) {
+            Err(LoadError::UnsupportedSchemaVersion(None)) => {},
+            x => panic!("Unexpected result: {:?}", x),
+        }
+    }
+}
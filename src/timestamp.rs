@@ -0,0 +1,44 @@
+//! RFC 3339 timestamps for the report
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Formats seconds since the Unix epoch as an RFC 3339 timestamp in UTC,
+/// such as `2017-11-04T13:37:00Z`
+pub fn format(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let secs_of_day = secs % 86400;
+
+    // Converts days since the epoch to a civil date, following
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day,
+        secs_of_day / 3600, secs_of_day / 60 % 60, secs_of_day % 60,
+    )
+}
+
+pub fn now() -> String {
+    format(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn can_format_timestamps() {
+        assert_eq!("1970-01-01T00:00:00Z", format(0));
+        assert_eq!("2000-02-29T12:34:56Z", format(951827696));
+        assert_eq!("2017-11-04T13:37:00Z", format(1509802620));
+    }
+}
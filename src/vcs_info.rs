@@ -0,0 +1,62 @@
+use std::path::Path;
+
+use serde_json;
+
+pub const VCS_INFO_FILE: &str = ".cargo_vcs_info.json";
+
+#[derive(Debug, Deserialize)]
+pub struct GitInfo {
+    pub sha1: String,
+}
+
+/// The contents of `.cargo_vcs_info.json`, which `cargo package` includes
+/// in crates packaged from a git repository. It pins down the exact commit
+/// the crate was published from and where in the repository it lives.
+#[derive(Debug, Deserialize)]
+pub struct VcsInfo {
+    pub git: GitInfo,
+
+    /// Path of the crate relative to the root of the repository. Missing in
+    /// crates packaged by older versions of cargo, in which case the crate
+    /// is likely to live in the root.
+    #[serde(default)]
+    pub path_in_vcs: String,
+}
+
+impl VcsInfo {
+    pub fn from_package_root(package_root: &Path) -> Option<VcsInfo> {
+        use std::fs::File;
+
+        let file = try_opt!(File::open(package_root.join(VCS_INFO_FILE)).ok());
+        serde_json::from_reader(file).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn can_deserialize_vcs_info() {
+        let x: VcsInfo = serde_json::from_str(r#"{
+          "git": {
+            "sha1": "f6b9bc7b3d4e1a6a4c1ad63e8e6c2e4e1cbab3c1"
+          },
+          "path_in_vcs": "futures-cpupool"
+        }"#).unwrap();
+
+        assert_eq!("f6b9bc7b3d4e1a6a4c1ad63e8e6c2e4e1cbab3c1", x.git.sha1);
+        assert_eq!("futures-cpupool", x.path_in_vcs);
+    }
+
+    #[test]
+    fn can_deserialize_vcs_info_without_path() {
+        let x: VcsInfo = serde_json::from_str(r#"{
+          "git": {
+            "sha1": "f6b9bc7b3d4e1a6a4c1ad63e8e6c2e4e1cbab3c1"
+          }
+        }"#).unwrap();
+
+        assert_eq!("", x.path_in_vcs);
+    }
+}